@@ -1,23 +1,290 @@
-use anyhow::Result;
-use clap::Parser;
-use countdown::expr::{all_ops, std_ops, Op, OpsType};
-use countdown::solver_v2::{dedup, solutions2};
+use anyhow::{bail, Result};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use countdown::check::check;
+use countdown::conundrum::Conundrums;
+use countdown::difficulty::difficulty;
+use countdown::expr::{all_ops, std_ops, Expr, Op, OpsType};
+use countdown::game::{answer_within, best_answer, DECLARE, SECONDS};
+use countdown::generate::{deal, rng};
+use countdown::letters::{deal_letters, points, Dictionary};
+use countdown::number::Number;
+use countdown::rank::rank_solutions;
+use countdown::rational::{solutions_rational_nearest, steps_rational};
+use countdown::reach::{reachable, unreachable};
+use countdown::rules::Rules;
+use countdown::solver_v2::{
+    dedup, solutions2_limit, solutions2_nearest, solutions2_nearest_par, solutions_iter,
+    Result as Soln,
+};
+use countdown::solver_v3::solutions3_nearest;
+use itertools::Itertools;
+use num_bigint::BigInt;
+use num_traits::Zero;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
 
-const ABOUT: &str = r#"Countdown numbers solver
+const ABOUT: &str = r#"Countdown numbers and letters solver
 
 Examples:
   countdown 25 50 75 100 3 6 -t 952          # A famous real game
   countdown 1 2 3 4 --op add --op mul -t 32  # Can use fewer numbers and operators
   countdown 1 2 3 4 --all-ops -t 32          # Can use extra operators
+  countdown 50 25 75 100 4 1 -t 608 --solver v3  # Memoised solver
+  countdown 1 3 4 6 -t 24 --solver fractions     # Allow fractional steps
+  countdown 2 32 4 -t 1073741824 --all-ops --int i64  # Wider intermediate values
+  countdown 2147483647 2 -t 4294967294 --int i64  # Wider tiles and target
+  countdown 3 7 -t=-4 --allow-negatives          # Relax the rules
+  countdown 25 50 75 100 3 6 -t 952 --official  # Every step a positive whole number
+  countdown 3 4 5 -t 29 --op add --op fact --op sqrt  # Unary operators too
+  countdown 4 4 4 4 -t 11 --op div --op cat --solver v3  # Join tiles, 44 / 4
+  countdown 50 25 75 100 4 1 -t 608 --threads 8  # Solve on 8 threads
+  countdown 50 25 75 100 4 1 -t 608 --stream     # Print solutions as they are found
+  countdown 50 25 75 100 4 1 -t 608 --first      # Stop at the first solution
+  countdown 50 25 75 100 4 1 -t 608 --limit 5    # Stop after 5 solutions
+  countdown 50 25 75 100 4 1 -t 608 --sort simplest  # Simplest solutions first
+  countdown 50 25 75 100 4 1 -t 608 --format steps  # Show working step by step
+  countdown 50 25 75 100 4 1 -t 608 --format pretty # Only necessary brackets
+  countdown 50 25 75 100 4 1 -t 608 --format json   # Machine readable, ndjson to stream
+  countdown 25 50 75 100 3 6 -t 952 --rate     # How hard is this game?
+  countdown deal --large 2 --seed 42           # Deal a reproducible game
+  countdown reach 25 50 75 100 3 6              # Which of 1 to 999 can be made?
+  countdown check 25 50 75 100 3 6 -t 952 -a "((100 + 6) * 3 * 75 - 50) / 25"
+  countdown letters GYHDNOEUR --dict words.txt  # Longest words in a letters round
+  countdown conundrum DOWNCOUNT --dict words.txt  # Unscramble a conundrum
+  countdown conundrum --dict words.txt --count 5  # Make new conundrums
+  countdown play --large 2 --dict words.txt     # Play a game against the clock
 "#;
 
-#[derive(Parser)]
-#[command(version = "1.0", about = ABOUT)]
-struct Countdown {
+#[derive(Debug, Clone, PartialEq, Eq, ValueEnum)]
+enum Solver {
+    V2,
+    V3,
+    Fractions,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, ValueEnum)]
+enum Int {
+    I32,
+    I64,
+    Big,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, ValueEnum)]
+enum Sort {
+    Found,
+    Simplest,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Text,
+    Pretty,
+    Steps,
+    Json,
+    Ndjson,
+}
+
+#[derive(Serialize)]
+struct SolnJson<'a> {
+    expr: String,
+    tree: &'a Expr,
+    value: i32,
+}
+
+#[derive(Serialize)]
+struct ReportJson<'a> {
+    tiles: &'a [i32],
+    target: i32,
+    ops: &'a OpsType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    solutions: Option<Vec<SolnJson<'a>>>,
+    found: usize,
+    unique: usize,
+    distance: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    checked: Option<usize>,
+    dups: Vec<String>,
+}
+
+#[derive(Args)]
+struct CheckArgs {
     #[arg(short = 't', long = "target", help = "Target value")]
     target: i32,
+    #[arg(help = "Drawn numbers")]
+    nums: Vec<i32>,
+    #[arg(short = 'a', long = "answer", help = "Player's expression")]
+    answer: String,
+    #[arg(long, default_value_t = false, help = "Allow [exp, mod] as well")]
+    all_ops: bool,
+}
+
+#[derive(Args)]
+struct DealArgs {
+    #[arg(
+        short = 'l',
+        long,
+        default_value_t = 1,
+        help = "Number of large tiles, 0 to 4"
+    )]
+    large: usize,
+    #[arg(long, help = "Seed for a reproducible deal")]
+    seed: Option<u64>,
+}
+
+#[derive(Args)]
+struct LettersArgs {
+    #[arg(help = "Drawn letters, e.g. GYHDNOEUR")]
+    letters: String,
+    #[arg(long, help = "Word list, one word per line")]
+    dict: PathBuf,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "List every word, not only the longest"
+    )]
+    all: bool,
+}
+
+#[derive(Args)]
+struct ConundrumArgs {
+    #[arg(help = "Scrambled nine letter word - a new conundrum is made if not given")]
+    scramble: Option<String>,
+    #[arg(long, help = "Word list, one word per line")]
+    dict: PathBuf,
+    #[arg(long, default_value_t = 1, help = "Number of conundrums to make")]
+    count: usize,
+    #[arg(long, help = "Seed for reproducible conundrums")]
+    seed: Option<u64>,
+}
+
+#[derive(Args)]
+struct PlayArgs {
+    #[arg(
+        short = 'l',
+        long,
+        default_value_t = 1,
+        help = "Number of large tiles, 0 to 4"
+    )]
+    large: usize,
+    #[arg(long, help = "Word list - also plays a letters round")]
+    dict: Option<PathBuf>,
+    #[arg(long, default_value_t = 4, help = "Number of vowels, 3 to 5")]
+    vowels: usize,
+    #[arg(long, default_value_t = SECONDS, help = "Seconds to answer each round")]
+    seconds: u64,
+    #[arg(long, help = "Seed for a reproducible game")]
+    seed: Option<u64>,
+}
+
+#[derive(Args)]
+struct ReachArgs {
     #[arg(help = "Allowed numbers, used once, can include duplicates")]
     nums: Vec<i32>,
+    #[arg(long, default_value_t = 1, help = "Smallest target")]
+    min: i32,
+    #[arg(long, default_value_t = 999, help = "Largest target")]
+    max: i32,
+    #[arg(long, default_value_t = false, help = "Allow [exp, mod] as well")]
+    all_ops: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "List the simplest way to make each target"
+    )]
+    list: bool,
+
+    #[command(flatten)]
+    rules: RulesArgs,
+}
+
+#[derive(Args)]
+struct RulesArgs {
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Official rules - no step may give zero or build on it"
+    )]
+    official: bool,
+    #[arg(long, default_value_t = false, help = "Steps may give negative values")]
+    allow_negatives: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Steps may give zero, even x - x"
+    )]
+    allow_zero: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Steps may give fractions (fractions solver)"
+    )]
+    allow_fractions: bool,
+    #[arg(long, default_value_t = false, help = "Allow x * 1, x / 1 and x ^ 1")]
+    allow_identity: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Let negative or zero values be used in later steps"
+    )]
+    any_operands: bool,
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(i64).range(0..),
+        help = "Largest value allowed at any step"
+    )]
+    max_value: Option<i64>,
+}
+
+impl RulesArgs {
+    // Each flag relaxes the chosen rules
+    fn rules(&self) -> Rules {
+        let rules = if self.official {
+            Rules::official()
+        } else {
+            Rules::default()
+        };
+
+        Rules {
+            allow_negatives: rules.allow_negatives || self.allow_negatives,
+            allow_zero: rules.allow_zero || self.allow_zero,
+            allow_fractions: rules.allow_fractions || self.allow_fractions,
+            allow_identity: rules.allow_identity || self.allow_identity,
+            allow_cancel: rules.allow_cancel || self.allow_zero,
+            require_positive: rules.require_positive && !self.any_operands,
+            max_value: self.max_value,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Check a player's answer")]
+    Check(CheckArgs),
+    #[command(about = "Deal a random game")]
+    Deal(DealArgs),
+    #[command(about = "Find every target the numbers can make")]
+    Reach(ReachArgs),
+    #[command(about = "Solve a letters round")]
+    Letters(LettersArgs),
+    #[command(about = "Solve or make a conundrum")]
+    Conundrum(ConundrumArgs),
+    #[command(about = "Play a game against the clock")]
+    Play(PlayArgs),
+}
+
+#[derive(Parser)]
+#[command(version = "1.0", about = ABOUT)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Countdown {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(short = 't', long = "target", required = true, help = "Target value")]
+    target: Option<BigInt>,
+    #[arg(help = "Allowed numbers, used once, can include duplicates")]
+    nums: Vec<BigInt>,
 
     #[arg(long = "op", name = "OP", action = clap::ArgAction::Append)]
     ops: Vec<Op>,
@@ -25,6 +292,36 @@ struct Countdown {
     std_ops: bool,
     #[arg(long, default_value_t = false, help = "[add, mul, sub, div, exp, mod]")]
     all_ops: bool,
+
+    #[command(flatten)]
+    rules: RulesArgs,
+
+    #[arg(long, value_enum, default_value_t = Solver::V2, help = "Solver implementation")]
+    solver: Solver,
+    #[arg(long, value_enum, default_value_t = Int::I32, help = "Integer type for values")]
+    int: Int,
+    #[arg(long, help = "Number of solver threads (v2 solver only)")]
+    threads: Option<usize>,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Print solutions as they are found"
+    )]
+    stream: bool,
+    #[arg(long, default_value_t = false, help = "Stop at the first solution")]
+    first: bool,
+    #[arg(long, help = "Stop after this many solutions")]
+    limit: Option<usize>,
+    #[arg(long, value_enum, default_value_t = Sort::Found, help = "Solution order")]
+    sort: Sort,
+    #[arg(long, value_enum, default_value_t = Format::Text, help = "Solution format")]
+    format: Format,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Rate the difficulty instead of solving"
+    )]
+    rate: bool,
 }
 
 // Invocation: CLAP provides help
@@ -32,11 +329,105 @@ struct Countdown {
 // countdown 50 25 75 100 4 1 -t 608
 //
 
-pub fn solve(target: i32, nums: &[i32], ops: &OpsType) {
-    let solns = solutions2(&nums, target, ops);
+fn soln_json(s: &Soln) -> SolnJson<'_> {
+    SolnJson {
+        expr: s.0.to_string(),
+        tree: &s.0,
+        value: s.1,
+    }
+}
+
+fn report<'a>(
+    tiles: &'a [i32],
+    target: i32,
+    ops: &'a OpsType,
+    solns: &'a [Soln],
+    distance: u32,
+    checked: Option<usize>,
+) -> ReportJson<'a> {
+    let (deduped, dups) = dedup(solns);
+
+    ReportJson {
+        tiles,
+        target,
+        ops,
+        solutions: Some(solns.iter().map(soln_json).collect()),
+        found: solns.len(),
+        unique: deduped.len(),
+        distance,
+        checked,
+        dups,
+    }
+}
+
+// Ndjson has a line per solution followed by the report without them
+fn print_report(mut report: ReportJson, format: &Format) {
+    if format == &Format::Ndjson {
+        report.solutions.take().into_iter().flatten().for_each(|s| {
+            println!("{}", serde_json::to_string(&s).unwrap());
+        });
+    }
+
+    println!("{}", serde_json::to_string(&report).unwrap());
+}
+
+fn show(s: &Soln, format: &Format) {
+    match format {
+        Format::Json | Format::Ndjson => {
+            println!("{}", serde_json::to_string(&soln_json(s)).unwrap())
+        }
+        Format::Text => println!("{} = {}", s.0, s.1),
+        Format::Pretty => println!("{} = {}", s.0.pretty(), s.1),
+        // Rational steps are the same as integer ones, and also show the
+        // fractions solver's working
+        Format::Steps => {
+            let steps = steps_rational(&s.0);
+
+            if steps.is_empty() {
+                println!("{}", s.1);
+            }
+
+            steps.iter().for_each(|step| println!("{step}"));
+            println!();
+        }
+    }
+}
+
+fn solve(countdown: &Countdown, target: i32, nums: &[i32], rules: &Rules) {
+    let ops = &countdown.ops;
+    let (sort, format) = (&countdown.sort, &countdown.format);
+
+    let ((mut nearest, distance), checked) = match (&countdown.solver, countdown.threads) {
+        (Solver::V2, Some(threads)) => solutions2_nearest_par(nums, target, ops, rules, threads),
+        (Solver::V2, None) => solutions2_nearest(nums, target, ops, rules),
+        (Solver::V3, _) => solutions3_nearest(nums, target, ops, rules),
+        (Solver::Fractions, _) => solutions_rational_nearest(nums, target, ops, rules),
+    };
+
+    if sort == &Sort::Simplest {
+        nearest = rank_solutions(&nearest)
+            .into_iter()
+            .map(|(s, _)| s)
+            .collect();
+    }
+
+    if matches!(format, Format::Json | Format::Ndjson) {
+        print_report(
+            report(nums, target, ops, &nearest, distance, Some(checked)),
+            format,
+        );
+        return;
+    }
+
+    if distance != 0 {
+        show_nearest(target, nums, &nearest, distance, checked, format);
+        return;
+    }
+
+    let solns = (nearest, checked);
 
     solns.0.iter().for_each(|s| {
-        println!("{} = {}", s.0, s.1);
+        show(s, format);
     });
 
     println!(
@@ -58,7 +449,7 @@ pub fn solve(target: i32, nums: &[i32], ops: &OpsType) {
         println!();
 
         deduped.iter().for_each(|s| {
-            println!("{} = {}", s.0, s.1);
+            show(s, format);
         });
 
         println!(
@@ -71,6 +462,408 @@ pub fn solve(target: i32, nums: &[i32], ops: &OpsType) {
     }
 }
 
+// Wider integer types only use the v2 and v3 solvers and plain output
+fn solve_wide<N: Number>(target: N, nums: &[N], countdown: &Countdown, rules: &Rules) {
+    let (ops, format) = (&countdown.ops, &countdown.format);
+
+    let ((solns, distance), checked) = match (&countdown.solver, countdown.threads) {
+        (Solver::V2, Some(threads)) => {
+            solutions2_nearest_par(nums, target.clone(), ops, rules, threads)
+        }
+        (Solver::V3, _) => solutions3_nearest(nums, target.clone(), ops, rules),
+        _ => solutions2_nearest(nums, target.clone(), ops, rules),
+    };
+
+    let show = |s: &Soln<N>| match format {
+        Format::Pretty => println!("{} = {}", s.0.pretty(), s.1),
+        _ => println!("{} = {}", s.0, s.1),
+    };
+
+    let (deduped, _) = dedup(&solns);
+
+    if !distance.is_zero() {
+        println!(
+            "0 solutions to make {} from {:?} - {} checked\n",
+            target, nums, checked
+        );
+
+        deduped.iter().for_each(show);
+
+        let vals = deduped.iter().map(|s| &s.1).sorted().dedup().join(", ");
+
+        println!("\nnearest: {} (off by {})", vals, distance);
+        return;
+    }
+
+    solns.iter().for_each(show);
+
+    println!(
+        "{} solutions to make {} from {:?} - {} checked",
+        solns.len(),
+        target,
+        nums,
+        checked
+    );
+
+    if deduped.len() != solns.len() {
+        println!("{} unique solutions", deduped.len());
+    }
+}
+
+// Values are parsed as BigInt so any --int can be given, then narrowed to fit
+fn narrow<N: Number>(val: &BigInt, int: &str) -> Result<N> {
+    match val.to_i64().and_then(N::from_i64) {
+        Some(val) => Ok(val),
+        None => bail!("{val} doesn't fit --int {int} - try a wider --int"),
+    }
+}
+
+fn narrow_all<N: Number>(target: &BigInt, nums: &[BigInt], int: &str) -> Result<(N, Vec<N>)> {
+    let nums = nums
+        .iter()
+        .map(|n| narrow(n, int))
+        .collect::<Result<Vec<N>>>()?;
+
+    Ok((narrow(target, int)?, nums))
+}
+
+fn stream(target: i32, nums: &[i32], ops: &OpsType, rules: &Rules, format: &Format) {
+    let mut found = Vec::<Soln>::new();
+
+    solutions_iter(nums, target, ops, rules).for_each(|s| {
+        if format != &Format::Json {
+            show(&s, format);
+        }
+        found.push(s);
+    });
+
+    match format {
+        Format::Json => print_report(report(nums, target, ops, &found, 0, None), format),
+        Format::Ndjson => {
+            let mut report = report(nums, target, ops, &found, 0, None);
+            report.solutions = None;
+
+            print_report(report, format);
+        }
+        _ => println!(
+            "{} solutions to make {} from {:?}",
+            found.len(),
+            target,
+            nums
+        ),
+    }
+}
+
+fn limited(target: i32, nums: &[i32], ops: &OpsType, rules: &Rules, limit: usize, format: &Format) {
+    let ((solns, distance), checked) = solutions2_limit(nums, target, ops, rules, limit);
+    let json = matches!(format, Format::Json | Format::Ndjson);
+
+    // Nothing found means every result was checked, and the nearest kept
+    if distance != 0 {
+        if json {
+            print_report(
+                report(nums, target, ops, &solns, distance, Some(checked)),
+                format,
+            );
+        } else {
+            show_nearest(target, nums, &solns, distance, checked, format);
+        }
+        return;
+    }
+
+    if json {
+        print_report(report(nums, target, ops, &solns, 0, Some(checked)), format);
+        return;
+    }
+
+    solns.iter().for_each(|s| {
+        show(s, format);
+    });
+
+    let stopped = if solns.len() == limit {
+        "stopped after "
+    } else {
+        ""
+    };
+
+    println!(
+        "{} solutions to make {} from {:?} - {}{} checked",
+        solns.len(),
+        target,
+        nums,
+        stopped,
+        checked
+    );
+}
+
+fn show_nearest(
+    target: i32,
+    nums: &[i32],
+    nearest: &[Soln],
+    distance: u32,
+    checked: usize,
+    format: &Format,
+) {
+    println!(
+        "0 solutions to make {} from {:?} - {} checked",
+        target, nums, checked
+    );
+
+    if nearest.is_empty() {
+        return;
+    }
+
+    let (deduped, _) = dedup(nearest);
+
+    println!();
+    deduped.iter().for_each(|s| {
+        show(s, format);
+    });
+
+    let vals = deduped.iter().map(|s| s.1).sorted().dedup().join(", ");
+
+    println!("\nnearest: {} (off by {})", vals, distance);
+}
+
+fn rate(target: i32, nums: &[i32], ops: &OpsType, rules: &Rules) {
+    let rating = difficulty(nums, target, ops, rules);
+
+    println!(
+        "{} to make {} from {:?} - difficulty {} ({})",
+        if rating.reachable {
+            "Possible"
+        } else {
+            "Impossible"
+        },
+        target,
+        nums,
+        rating.score,
+        rating.level()
+    );
+
+    if rating.reachable {
+        println!("  unique solutions: {}", rating.solutions);
+        println!("  fewest tiles: {}", rating.min_tiles);
+        println!("  division needed: {}", rating.needs_div);
+        println!("  large intermediates needed: {}", rating.needs_large);
+    }
+}
+
+fn check_answer(args: &CheckArgs) {
+    let ops = if args.all_ops { all_ops() } else { std_ops() };
+
+    match check(&args.nums, args.target, &args.answer, &ops) {
+        Ok(checked) => println!(
+            "{} = {} - off by {}, {} points",
+            checked.expr, checked.value, checked.distance, checked.points
+        ),
+        Err(e) => println!("INVALID answer: {e} - 0 points"),
+    }
+}
+
+fn deal_game(args: &DealArgs) -> Result<()> {
+    let deal = deal(args.large, args.seed)?;
+
+    println!("{} -t {}", deal.tiles.iter().join(" "), deal.target);
+
+    Ok(())
+}
+
+fn letters_round(args: &LettersArgs) -> Result<()> {
+    let dict = Dictionary::load(&args.dict)?;
+
+    let words = if args.all {
+        dict.solutions(&args.letters)?
+    } else {
+        dict.longest(&args.letters)?
+    };
+
+    if words.is_empty() {
+        println!("No words can be made from {}", args.letters);
+    }
+
+    words
+        .iter()
+        .for_each(|w| println!("{w} - {} points", points(w)));
+
+    Ok(())
+}
+
+fn conundrum(args: &ConundrumArgs) -> Result<()> {
+    let conundrums = Conundrums::new(&Dictionary::load(&args.dict)?);
+
+    if let Some(scramble) = &args.scramble {
+        let answers = conundrums.solve(scramble)?;
+
+        if answers.is_empty() {
+            println!("No answer for {scramble}");
+        }
+
+        answers.iter().for_each(|a| println!("{a}"));
+
+        return Ok(());
+    }
+
+    // One generator for every conundrum, so a seed gives the same list each time
+    let mut rng = rng(args.seed);
+
+    for _ in 0..args.count {
+        let Some(c) = conundrums.generate_with(&mut rng) else {
+            bail!("No nine letter word in the dictionary has a single anagram");
+        };
+
+        println!("{} - {}", c.scramble, c.answer);
+    }
+
+    Ok(())
+}
+
+// Waits out the clock, then lets the player declare if they haven't yet
+fn timed_answer(lines: &Receiver<String>, seconds: u64) -> String {
+    // Lines typed during an earlier round don't count for this one
+    while lines.try_recv().is_ok() {}
+
+    let answer = answer_within(lines, seconds, |left| {
+        if left % 10 == 0 || left <= 5 {
+            println!("  {left}...");
+        }
+    });
+
+    answer
+        .or_else(|| {
+            println!("Time's up! Your answer ({DECLARE} seconds):");
+            answer_within(lines, DECLARE, |_| ())
+        })
+        .unwrap_or_default()
+}
+
+fn play_numbers(args: &PlayArgs, lines: &Receiver<String>) -> Result<u32> {
+    let deal = deal(args.large, args.seed)?;
+
+    println!("Numbers: {}", deal.tiles.iter().join(" "));
+    println!("Target: {}\n", deal.target);
+
+    let answer = timed_answer(lines, args.seconds);
+
+    let points = match check(&deal.tiles, deal.target, &answer, &std_ops()) {
+        _ if answer.trim().is_empty() => {
+            println!("No answer - 0 points");
+            0
+        }
+        Ok(checked) => {
+            println!(
+                "{} = {} - off by {}, {} points",
+                checked.expr, checked.value, checked.distance, checked.points
+            );
+            checked.points
+        }
+        Err(e) => {
+            println!("INVALID answer: {e} - 0 points");
+            0
+        }
+    };
+
+    match best_answer(&deal.tiles, deal.target) {
+        Some(((expr, val), 0)) => println!("Best answer: {} = {}", expr.pretty(), val),
+        Some(((expr, val), distance)) => println!(
+            "Best answer: {} = {} - {} away",
+            expr.pretty(),
+            val,
+            distance
+        ),
+        None => println!("No answer can be made"),
+    }
+
+    Ok(points)
+}
+
+fn play_letters(args: &PlayArgs, dict: &Dictionary, lines: &Receiver<String>) -> Result<u32> {
+    let letters = deal_letters(args.vowels, args.seed)?;
+
+    println!("Letters: {letters}\n");
+
+    let word = timed_answer(lines, args.seconds);
+    let points = dict.score(&letters, &word);
+
+    if word.trim().is_empty() {
+        println!("No word - 0 points");
+    } else if points > 0 {
+        println!("{} - {} points", word.trim().to_ascii_uppercase(), points);
+    } else {
+        println!("INVALID word: {} - 0 points", word.trim());
+    }
+
+    match dict.longest(&letters)?.as_slice() {
+        [] => println!("No words can be made"),
+        longest => println!("Longest words: {}", longest.join(" ")),
+    }
+
+    Ok(points as u32)
+}
+
+fn play(args: &PlayArgs) -> Result<()> {
+    // Loaded first so a bad path doesn't waste a round
+    let dict = args.dict.as_ref().map(Dictionary::load).transpose()?;
+
+    // Lines are read on their own thread so the clock can keep running
+    let (tx, lines) = channel();
+    thread::spawn(move || {
+        for line in std::io::stdin().lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut total = play_numbers(args, &lines)?;
+
+    if let Some(dict) = &dict {
+        println!();
+        total += play_letters(args, dict, &lines)?;
+    }
+
+    println!("\nTotal: {total} points");
+
+    Ok(())
+}
+
+fn reach(args: &ReachArgs) -> Result<()> {
+    if args.rules.allow_fractions {
+        bail!("reach only makes whole number steps - --allow-fractions isn't supported");
+    }
+
+    let ops = if args.all_ops { all_ops() } else { std_ops() };
+    let range = args.min..=args.max;
+
+    let reach = reachable(&args.nums, &ops, &args.rules.rules(), range.clone());
+    let missing = unreachable(&reach, range.clone());
+
+    if args.list {
+        reach.iter().for_each(|(val, r)| {
+            println!("{} = {} - {} ways", r.best, val, r.count);
+        });
+        println!();
+    }
+
+    let total = range.count();
+
+    println!(
+        "{} of {} targets from {} to {} can be made from {:?} ({:.1}%)",
+        reach.len(),
+        total,
+        args.min,
+        args.max,
+        args.nums,
+        100.0 * reach.len() as f64 / total.max(1) as f64
+    );
+
+    if !missing.is_empty() {
+        println!("\nUnreachable: {}", missing.iter().join(" "));
+    }
+
+    Ok(())
+}
+
 fn validate_args(countdown: &mut Countdown) -> Result<bool> {
     if countdown.all_ops && countdown.ops.is_empty() {
         countdown.ops = all_ops();
@@ -88,15 +881,120 @@ fn validate_args(countdown: &mut Countdown) -> Result<bool> {
         countdown.ops = ops;
     }
 
+    if countdown.first && countdown.limit.is_none() {
+        countdown.limit = Some(1);
+    }
+
+    if countdown.rules.allow_fractions {
+        countdown.solver = Solver::Fractions;
+    } else if countdown.solver == Solver::Fractions {
+        countdown.rules.allow_fractions = true;
+    }
+
+    if countdown.rate && countdown.solver == Solver::Fractions {
+        bail!("--rate only rates whole number steps - fractions aren't supported");
+    }
+
+    if countdown.threads.is_some() && (countdown.solver != Solver::V2 || countdown.rate) {
+        bail!("--threads is only supported by the v2 solver");
+    }
+
+    if countdown.stream
+        && (countdown.solver != Solver::V2
+            || countdown.sort != Sort::Found
+            || countdown.threads.is_some())
+    {
+        bail!("--stream only supports the v2 solver on one thread, in found order");
+    }
+
+    if countdown.limit.is_some()
+        && (countdown.solver != Solver::V2
+            || countdown.sort != Sort::Found
+            || countdown.threads.is_some())
+    {
+        bail!("--limit and --first only support the v2 solver on one thread, in found order");
+    }
+
+    if countdown.int != Int::I32
+        && (countdown.solver == Solver::Fractions
+            || countdown.stream
+            || countdown.limit.is_some()
+            || countdown.rate
+            || countdown.sort != Sort::Found
+            || !matches!(countdown.format, Format::Text | Format::Pretty))
+    {
+        bail!(
+            "--int i64 and big only support the v2 and v3 solvers with text or pretty output \
+             - --stream, --limit, --rate, --sort and fractions need --int i32"
+        );
+    }
+
+    if countdown.ops.iter().any(|op| op.is_unary())
+        && (countdown.solver != Solver::V2 || countdown.rate)
+    {
+        bail!("--op fact, sqrt and neg are only supported by the v2 solver");
+    }
+
     Ok(true)
 }
 
 pub fn main() -> Result<()> {
     let mut countdown = Countdown::parse();
 
+    if let Some(command) = &countdown.command {
+        match command {
+            Command::Check(args) => check_answer(args),
+            Command::Deal(args) => deal_game(args)?,
+            Command::Reach(args) => reach(args)?,
+            Command::Letters(args) => letters_round(args)?,
+            Command::Conundrum(args) => conundrum(args)?,
+            Command::Play(args) => play(args)?,
+        }
+
+        return Ok(());
+    }
+
     validate_args(&mut countdown)?;
 
-    solve(countdown.target, &countdown.nums, &countdown.ops);
+    let Some(target) = &countdown.target else {
+        bail!("A target is required");
+    };
+
+    let rules = countdown.rules.rules();
+
+    match countdown.int {
+        Int::I32 => (),
+        Int::I64 => {
+            let (target, nums) = narrow_all::<i64>(target, &countdown.nums, "i64")?;
+
+            solve_wide(target, &nums, &countdown, &rules);
+            return Ok(());
+        }
+        Int::Big => {
+            solve_wide(target.clone(), &countdown.nums, &countdown, &rules);
+            return Ok(());
+        }
+    }
+
+    let (target, nums) = narrow_all::<i32>(target, &countdown.nums, "i32")?;
+    let (nums, ops, format) = (&nums, &countdown.ops, &countdown.format);
+
+    if countdown.rate {
+        rate(target, nums, ops, &rules);
+        return Ok(());
+    }
+
+    if countdown.stream {
+        stream(target, nums, ops, &rules, format);
+        return Ok(());
+    }
+
+    if let Some(limit) = countdown.limit {
+        limited(target, nums, ops, &rules, limit, format);
+        return Ok(());
+    }
+
+    solve(&countdown, target, nums, &rules);
 
     Ok(())
 }
@@ -1,14 +1,62 @@
 #[path = "lib/expr.rs"]
 pub mod expr;
 
+#[allow(clippy::len_zero, clippy::needless_borrow, clippy::vec_init_then_push)]
 #[path = "lib/combinatorics.rs"]
 pub mod combinatorics;
 
 #[path = "lib/solver_v2.rs"]
 pub mod solver_v2;
 
+#[allow(
+    clippy::clone_on_copy,
+    clippy::needless_borrow,
+    clippy::vec_init_then_push
+)]
 #[path = "lib/solver_v1.rs"]
 pub mod solver_v1;
 
 #[path = "lib/parser.rs"]
 pub mod parser;
+
+#[path = "lib/solver_v3.rs"]
+pub mod solver_v3;
+
+#[path = "lib/rank.rs"]
+pub mod rank;
+
+#[path = "lib/canon.rs"]
+pub mod canon;
+
+#[path = "lib/check.rs"]
+pub mod check;
+
+#[path = "lib/generate.rs"]
+pub mod generate;
+
+#[path = "lib/difficulty.rs"]
+pub mod difficulty;
+
+#[path = "lib/reach.rs"]
+pub mod reach;
+
+#[path = "lib/rational.rs"]
+pub mod rational;
+
+#[path = "lib/number.rs"]
+pub mod number;
+
+#[path = "lib/rules.rs"]
+pub mod rules;
+
+#[path = "lib/operator.rs"]
+pub mod operator;
+
+#[path = "lib/letters.rs"]
+pub mod letters;
+
+#[path = "lib/conundrum.rs"]
+pub mod conundrum;
+
+#[path = "lib/game.rs"]
+pub mod game;
@@ -0,0 +1,134 @@
+use crate::expr::{Expr, Op};
+use crate::number::Number;
+
+// Canonical normal form for expressions - used for exact duplicate removal
+//
+// Chains of Add/Sub are flattened into a Sum of added and subtracted terms and
+// chains of Mul/Div into a Product of multiplied and divided factors. Each group
+// is sorted, so any reordering or regrouping of the same terms compares equal.
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CanonExpr<N = i32> {
+    Val(N),
+    Sum(Vec<CanonExpr<N>>, Vec<CanonExpr<N>>),
+    Product(Vec<CanonExpr<N>>, Vec<CanonExpr<N>>),
+    Expr(Op, Box<CanonExpr<N>>, Box<CanonExpr<N>>),
+    Unary(Op, Box<CanonExpr<N>>),
+}
+
+// Operators that flatten into a group - see Operator::group
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Group {
+    Sum,
+    Product,
+}
+
+// Split a canonical expression into its positive and negative parts for a group
+fn terms<N>(e: CanonExpr<N>, group: Group) -> (Vec<CanonExpr<N>>, Vec<CanonExpr<N>>) {
+    match (e, group) {
+        (CanonExpr::Sum(pos, neg), Group::Sum) => (pos, neg),
+        (CanonExpr::Product(pos, neg), Group::Product) => (pos, neg),
+        (e, _) => (vec![e], vec![]),
+    }
+}
+
+fn combine<N: Ord>(a: CanonExpr<N>, b: CanonExpr<N>, group: Group, inverse: bool) -> CanonExpr<N> {
+    let (mut pos, mut neg) = terms(a, group);
+    let (b_pos, b_neg) = terms(b, group);
+
+    if inverse {
+        pos.extend(b_neg);
+        neg.extend(b_pos);
+    } else {
+        pos.extend(b_pos);
+        neg.extend(b_neg);
+    }
+
+    pos.sort();
+    neg.sort();
+
+    match group {
+        Group::Sum => CanonExpr::Sum(pos, neg),
+        Group::Product => CanonExpr::Product(pos, neg),
+    }
+}
+
+pub fn canonicalize<N: Number>(expr: &Expr<N>) -> CanonExpr<N> {
+    match expr {
+        Expr::Val(v) => CanonExpr::Val(v.clone()),
+        Expr::Expr(op, a, b) => {
+            let a = canonicalize(a);
+            let b = canonicalize(b);
+
+            let operator = op.operator();
+
+            match (operator.arity(), operator.group()) {
+                (1, _) => unreachable!("Unary {op} used as binary"),
+                (_, Some((group, inverse))) => combine(a, b, group, inverse),
+                (_, None) => {
+                    let (a, b) = if operator.commutative() && b < a {
+                        (b, a)
+                    } else {
+                        (a, b)
+                    };
+
+                    CanonExpr::Expr(op.clone(), Box::new(a), Box::new(b))
+                }
+            }
+        }
+        Expr::Unary(op, a) => CanonExpr::Unary(op.clone(), Box::new(canonicalize(a))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn val(v: i32) -> Expr {
+        Expr::new_val(v)
+    }
+
+    #[test]
+    fn mul_regrouped() {
+        // 2 * (3 * 5) == (2 * 3) * 5
+        let e1 = Expr::new_expr(Op::Mul, val(2), Expr::new_expr(Op::Mul, val(3), val(5)));
+        let e2 = Expr::new_expr(Op::Mul, Expr::new_expr(Op::Mul, val(2), val(3)), val(5));
+
+        assert_eq!(canonicalize(&e1), canonicalize(&e2));
+    }
+
+    #[test]
+    fn sub_regrouped() {
+        // 10 - (7 - 2) == (10 + 2) - 7
+        let e1 = Expr::new_expr(Op::Sub, val(10), Expr::new_expr(Op::Sub, val(7), val(2)));
+        let e2 = Expr::new_expr(Op::Sub, Expr::new_expr(Op::Add, val(10), val(2)), val(7));
+
+        assert_eq!(canonicalize(&e1), canonicalize(&e2));
+    }
+
+    #[test]
+    fn div_regrouped() {
+        // 100 / (10 / 2) == (100 * 2) / 10
+        let e1 = Expr::new_expr(Op::Div, val(100), Expr::new_expr(Op::Div, val(10), val(2)));
+        let e2 = Expr::new_expr(Op::Div, Expr::new_expr(Op::Mul, val(100), val(2)), val(10));
+
+        assert_eq!(canonicalize(&e1), canonicalize(&e2));
+    }
+
+    #[test]
+    fn sub_ne_reversed() {
+        let e1 = Expr::new_expr(Op::Sub, val(10), val(100));
+        let e2 = Expr::new_expr(Op::Sub, val(100), val(10));
+
+        assert_ne!(canonicalize(&e1), canonicalize(&e2));
+    }
+
+    #[test]
+    fn add_mul_distinct() {
+        // 2 + (3 * 5) != (2 + 3) * 5
+        let e1 = Expr::new_expr(Op::Add, val(2), Expr::new_expr(Op::Mul, val(3), val(5)));
+        let e2 = Expr::new_expr(Op::Mul, Expr::new_expr(Op::Add, val(2), val(3)), val(5));
+
+        assert_ne!(canonicalize(&e1), canonicalize(&e2));
+    }
+}
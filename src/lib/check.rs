@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::expr::{check_concat, EvalError, Expr, Op, OpsType};
+use crate::parser::{parse, ParseError};
+use crate::rules::Rules;
+
+// Judging a player's answer against the drawn tiles and target
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CheckError {
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    #[error("{0} is not an available tile")]
+    Tile(i32),
+    #[error("{0} is not an allowed operation")]
+    Op(Op),
+    #[error(transparent)]
+    Eval(#[from] EvalError),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checked {
+    pub expr: Expr,
+    pub value: i32,
+    pub distance: u32,
+    pub points: u32,
+}
+
+// Official scoring - exact, within 5 and within 10
+pub fn points(distance: u32) -> u32 {
+    match distance {
+        0 => 10,
+        1..=5 => 7,
+        6..=10 => 5,
+        _ => 0,
+    }
+}
+
+// Players may write operands in either order and use identity operations,
+// which the solver only rejects to prune its search
+fn player_rules() -> Rules {
+    Rules {
+        allow_identity: true,
+        ..Rules::official()
+    }
+}
+
+fn tiles_used(expr: &Expr, used: &mut Vec<i32>) {
+    match expr {
+        Expr::Val(v) => used.push(*v),
+        Expr::Expr(_, a, b) => {
+            tiles_used(a, used);
+            tiles_used(b, used);
+        }
+        Expr::Unary(_, a) => tiles_used(a, used),
+    }
+}
+
+fn value(expr: &Expr, ops: &OpsType, rules: &Rules) -> Result<i32, CheckError> {
+    match expr {
+        Expr::Val(v) => Ok(*v),
+        Expr::Expr(op, a, b) => {
+            if !ops.contains(op) {
+                return Err(CheckError::Op(op.clone()));
+            }
+
+            check_concat(op, a, b)?;
+
+            let a = value(a, ops, rules)?;
+            let b = value(b, ops, rules)?;
+
+            Ok(rules.apply(op, &a, &b)?)
+        }
+        Expr::Unary(op, a) => {
+            if !ops.contains(op) {
+                return Err(CheckError::Op(op.clone()));
+            }
+
+            Ok(rules.apply_unary(op, &value(a, ops, rules)?)?)
+        }
+    }
+}
+
+pub fn check_expr(
+    tiles: &[i32],
+    target: i32,
+    expr: Expr,
+    ops: &OpsType,
+) -> Result<Checked, CheckError> {
+    let mut available = HashMap::<i32, usize>::new();
+    tiles
+        .iter()
+        .for_each(|t| *available.entry(*t).or_default() += 1);
+
+    let mut used = Vec::<i32>::new();
+    tiles_used(&expr, &mut used);
+
+    for tile in used {
+        match available.get_mut(&tile) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => return Err(CheckError::Tile(tile)),
+        }
+    }
+
+    let value = value(&expr, ops, &player_rules())?;
+    let distance = value.abs_diff(target);
+
+    Ok(Checked {
+        expr,
+        value,
+        distance,
+        points: points(distance),
+    })
+}
+
+pub fn check(
+    tiles: &[i32],
+    target: i32,
+    answer: &str,
+    ops: &OpsType,
+) -> Result<Checked, CheckError> {
+    check_expr(tiles, target, parse(answer)?, ops)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::std_ops;
+
+    const TILES: [i32; 6] = [25, 50, 75, 100, 3, 6];
+
+    #[test]
+    fn famous_952() {
+        let res = check(&TILES, 952, "(100 + 6) * 3 * 75 - 50 / 25", &std_ops()).unwrap();
+
+        // NOTE: Not exact - 23848 - 2
+        assert_eq!(res.value, 23848);
+        assert_eq!(res.points, 0);
+
+        let res = check(&TILES, 952, "((100 + 6) * 3 * 75 - 50) / 25", &std_ops()).unwrap();
+
+        assert_eq!(res.value, 952);
+        assert_eq!(res.points, 10);
+    }
+
+    #[test]
+    fn close_enough() {
+        let res = check(&TILES, 952, "100 * 6 + 75 * 3 + 50 + 25", &std_ops()).unwrap();
+
+        assert_eq!(res.value, 900);
+        assert_eq!(res.points, 0);
+
+        let res = check(&TILES, 952, "(6 + 3) * 100 + 50", &std_ops()).unwrap();
+
+        assert_eq!(res.distance, 2);
+        assert_eq!(res.points, 7);
+    }
+
+    #[test]
+    fn tile_reused() {
+        let res = check(&TILES, 952, "100 * 100", &std_ops());
+
+        assert_eq!(res, Err(CheckError::Tile(100)));
+    }
+
+    #[test]
+    fn tile_missing() {
+        let res = check(&TILES, 952, "100 * 7", &std_ops());
+
+        assert_eq!(res, Err(CheckError::Tile(7)));
+    }
+
+    #[test]
+    fn negative_step() {
+        let res = check(&TILES, 952, "3 - 6 + 100", &std_ops());
+
+        assert_eq!(
+            res,
+            Err(CheckError::Eval(EvalError::Rule("3 - 6".to_string())))
+        );
+    }
+
+    #[test]
+    fn inexact_division() {
+        let res = check(&TILES, 952, "100 / 3", &std_ops());
+
+        assert_eq!(
+            res,
+            Err(CheckError::Eval(EvalError::NotExact("100 / 3".to_string())))
+        );
+    }
+
+    #[test]
+    fn overflow_step() {
+        let ops = [Op::Mul, Op::Exp].to_vec();
+        let res = check(&[100, 50, 6], 952, "100 ^ 50 * 6", &ops);
+
+        assert_eq!(
+            res,
+            Err(CheckError::Eval(EvalError::Overflow(
+                "100 ^ 50".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    fn op_not_allowed() {
+        let res = check(&TILES, 952, "6 ^ 3", &std_ops());
+
+        assert_eq!(res, Err(CheckError::Op(Op::Exp)));
+    }
+
+    #[test]
+    fn any_operand_order() {
+        let res = check(&TILES, 406, "100 * 3 + 6 + 100 / 100", &std_ops());
+
+        assert_eq!(res, Err(CheckError::Tile(100)));
+
+        let res = check(&TILES, 306, "100 * 3 + 6", &std_ops()).unwrap();
+
+        assert_eq!(res.points, 10);
+    }
+}
@@ -1,3 +1,5 @@
+use itertools::Itertools;
+
 fn subs<T>(src: &[T]) -> Vec<Vec<T>>
 where
     T: Clone,
@@ -125,16 +127,41 @@ where
     res
 }
 
+// Lazy versions of choices and split - nothing is built until it is asked for
+
+pub fn choices_iter<T>(src: &[T]) -> impl Iterator<Item = Vec<T>>
+where
+    T: Clone,
+{
+    let src = src.to_vec();
+
+    src.into_iter()
+        .powerset()
+        .filter(|v| !v.is_empty())
+        .flat_map(|v| {
+            let len = v.len();
+            v.into_iter().permutations(len)
+        })
+}
+
+pub fn split_iter<T>(src: &[T]) -> impl Iterator<Item = (Vec<T>, Vec<T>)>
+where
+    T: Clone,
+{
+    let src = src.to_vec();
+
+    (1..src.len()).map(move |i| (src[..i].to_vec(), src[i..].to_vec()))
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
-    use itertools::Itertools;
 
     #[test]
     fn empty() {
         let res = subs::<usize>(&[]);
-        assert_eq!(res, vec![vec![]]);
+        assert_eq!(res, vec![Vec::<usize>::new()]);
     }
 
     #[test]
@@ -212,4 +239,22 @@ mod tests {
 
         assert_eq!(res, exp);
     }
+
+    #[test]
+    fn split_iter_matches() {
+        let input = [1, 2, 3, 4];
+
+        assert_eq!(split_iter(&input).collect::<Vec<_>>(), split(&input));
+        assert_eq!(split_iter::<usize>(&[]).count(), 0);
+    }
+
+    #[test]
+    fn choices_iter_matches() {
+        let input = [1, 2, 3, 4];
+
+        let res = choices_iter(&input).sorted().collect::<Vec<_>>();
+        let exp = choices(&input).into_iter().sorted().collect::<Vec<_>>();
+
+        assert_eq!(res, exp);
+    }
 }
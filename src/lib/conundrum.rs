@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::generate::rng;
+use crate::letters::{counts, Dictionary, LettersError, LETTERS};
+
+// Conundrums - a scrambled nine letter word to unscramble
+//
+// Nine letter words are indexed by their letters in sorted order, so every
+// anagram of a scramble is found with a single lookup.
+
+// Shuffles tried in case they only give back the answer
+const SHUFFLES: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conundrum {
+    pub scramble: String,
+    pub answer: String,
+}
+
+pub fn signature(word: &str) -> String {
+    let mut letters = word.to_ascii_uppercase().into_bytes();
+    letters.sort();
+
+    String::from_utf8(letters).unwrap()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Conundrums {
+    index: HashMap<String, Vec<String>>,
+    answers: Vec<String>,
+}
+
+impl Conundrums {
+    pub fn new(dict: &Dictionary) -> Self {
+        let mut index = HashMap::<String, Vec<String>>::new();
+
+        dict.words()
+            .filter(|w| w.len() == LETTERS)
+            .for_each(|w| index.entry(signature(w)).or_default().push(w.to_string()));
+
+        let mut answers = index
+            .values()
+            .filter(|words| words.len() == 1)
+            .map(|words| words[0].clone())
+            .collect::<Vec<_>>();
+
+        answers.sort();
+
+        Conundrums { index, answers }
+    }
+
+    // Every word the scramble is an anagram of, other than itself
+    pub fn solve(&self, scramble: &str) -> Result<Vec<&str>, LettersError> {
+        if scramble.len() != LETTERS || counts(scramble).is_none() {
+            return Err(LettersError::Conundrum(scramble.to_string()));
+        }
+
+        let scramble = scramble.to_ascii_uppercase();
+
+        Ok(self
+            .index
+            .get(&signature(&scramble))
+            .into_iter()
+            .flatten()
+            .filter(|w| **w != scramble)
+            .map(String::as_str)
+            .collect())
+    }
+
+    // Words with no other anagram, so the conundrum has a single answer
+    pub fn answers(&self) -> &[String] {
+        &self.answers
+    }
+
+    // A scramble of a random answer - never a word itself, as the answer has
+    // no other anagrams
+    pub fn generate_with<R: Rng>(&self, rng: &mut R) -> Option<Conundrum> {
+        for _ in 0..SHUFFLES {
+            let answer = self.answers.choose(rng)?;
+            let mut letters = answer.as_bytes().to_vec();
+
+            letters.shuffle(rng);
+
+            let scramble = String::from_utf8(letters).unwrap();
+
+            if &scramble != answer {
+                return Some(Conundrum {
+                    scramble,
+                    answer: answer.clone(),
+                });
+            }
+        }
+
+        None
+    }
+
+    pub fn generate(&self, seed: Option<u64>) -> Option<Conundrum> {
+        self.generate_with(&mut rng(seed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conundrums() -> Conundrums {
+        Conundrums::new(&Dictionary::from_words([
+            "countdown",
+            "greyhound",
+            "auctioned",
+            "education",
+            "cautioned",
+            "seat",
+        ]))
+    }
+
+    #[test]
+    fn solve() {
+        let conundrums = conundrums();
+
+        assert_eq!(conundrums.solve("DOWNCOUNT").unwrap(), vec!["COUNTDOWN"]);
+        assert_eq!(conundrums.solve("houndgrey").unwrap(), vec!["GREYHOUND"]);
+        assert_eq!(conundrums.solve("EDUCATION").unwrap().len(), 2);
+        assert!(conundrums.solve("ABCDEFGHI").unwrap().is_empty());
+        assert!(matches!(
+            conundrums.solve("SEAT"),
+            Err(LettersError::Conundrum(_))
+        ));
+    }
+
+    #[test]
+    fn generate_single_answer() {
+        let conundrums = conundrums();
+
+        assert_eq!(conundrums.answers(), vec!["COUNTDOWN", "GREYHOUND"]);
+
+        (0..20).for_each(|seed| {
+            let conundrum = conundrums.generate(Some(seed)).unwrap();
+
+            assert_ne!(conundrum.scramble, conundrum.answer);
+            assert_eq!(
+                conundrums.solve(&conundrum.scramble).unwrap(),
+                vec![conundrum.answer.as_str()]
+            );
+        });
+
+        assert_eq!(conundrums.generate(Some(7)), conundrums.generate(Some(7)));
+        assert_eq!(Conundrums::default().generate(None), None);
+    }
+}
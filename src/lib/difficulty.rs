@@ -0,0 +1,110 @@
+use crate::expr::{Expr, Op, OpsType};
+use crate::rank::score;
+use crate::rules::Rules;
+use crate::solver_v2::dedup;
+use crate::solver_v3::solutions3;
+
+// Puzzle difficulty - from 0 (trivial) to 100 (target can't be reached)
+
+// Intermediate values above this are hard to work with mentally
+pub const LARGE_INTERMEDIATE: i32 = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difficulty {
+    pub reachable: bool,
+    pub solutions: usize,
+    pub min_tiles: usize,
+    pub needs_div: bool,
+    pub needs_large: bool,
+    pub score: u32,
+}
+
+impl Difficulty {
+    pub fn level(&self) -> &'static str {
+        match self.score {
+            0..=24 => "easy",
+            25..=49 => "medium",
+            50..=99 => "hard",
+            _ => "impossible",
+        }
+    }
+}
+
+fn uses_div(expr: &Expr) -> bool {
+    match expr {
+        Expr::Val(_) => false,
+        Expr::Expr(op, a, b) => op == &Op::Div || uses_div(a) || uses_div(b),
+        Expr::Unary(_, a) => uses_div(a),
+    }
+}
+
+pub fn difficulty(input: &[i32], target: i32, ops: &OpsType, rules: &Rules) -> Difficulty {
+    let (solns, _) = solutions3(input, target, ops, rules);
+    let (unique, _) = dedup(&solns);
+
+    if unique.is_empty() {
+        return Difficulty {
+            reachable: false,
+            solutions: 0,
+            min_tiles: 0,
+            needs_div: false,
+            needs_large: false,
+            score: 100,
+        };
+    }
+
+    let scores = unique.iter().map(|s| score(&s.0)).collect::<Vec<_>>();
+
+    let min_tiles = scores.iter().map(|s| s.tiles).min().unwrap_or(0);
+    let needs_div = unique.iter().all(|s| uses_div(&s.0));
+    let needs_large = scores.iter().all(|s| s.largest > LARGE_INTERMEDIATE);
+
+    // Fewer solutions and more tiles make a harder puzzle
+    let rarity = (40.0 / (unique.len() as f64).sqrt()) as u32;
+    let tiles = 6 * (min_tiles.saturating_sub(1) as u32);
+    let awkward = 10 * (needs_div as u32 + needs_large as u32);
+
+    Difficulty {
+        reachable: true,
+        solutions: unique.len(),
+        min_tiles,
+        needs_div,
+        needs_large,
+        score: (rarity + tiles + awkward).min(99),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::std_ops;
+
+    #[test]
+    fn easy_target() {
+        let res = difficulty(&[25, 50, 75, 100, 3, 6], 150, &std_ops(), &Rules::default());
+
+        assert!(res.reachable);
+        assert_eq!(res.min_tiles, 2);
+        assert_eq!(res.level(), "easy");
+    }
+
+    #[test]
+    fn famous_952_hard() {
+        let res = difficulty(&[25, 50, 75, 100, 3, 6], 952, &std_ops(), &Rules::default());
+
+        assert!(res.reachable);
+        assert!(res.needs_div);
+        assert!(
+            res.score
+                > difficulty(&[25, 50, 75, 100, 3, 6], 150, &std_ops(), &Rules::default()).score
+        );
+    }
+
+    #[test]
+    fn unreachable() {
+        let res = difficulty(&[1, 2], 10, &std_ops(), &Rules::default());
+
+        assert!(!res.reachable);
+        assert_eq!(res.level(), "impossible");
+    }
+}
@@ -1,7 +1,14 @@
 use clap::ValueEnum;
+use serde::{Serialize, Serializer};
 use std::fmt::Display;
+use std::sync::Arc;
+use thiserror::Error;
 
-#[derive(Debug, Clone, PartialEq, Eq, ValueEnum)]
+use crate::number::Number;
+use crate::operator::{find, operator, Assoc, CustomOp, Operator};
+use crate::rules::Rules;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, ValueEnum)]
 pub enum Op {
     Add,
     Sub,
@@ -9,6 +16,30 @@ pub enum Op {
     Div,
     Exp,
     Mod,
+    // Digit concatenation - only joins tiles, so 1 || 2 is 12
+    Cat,
+    // Unary - applied to a single operand
+    Fact,
+    Sqrt,
+    Neg,
+    // Registered with operator::register
+    #[value(skip)]
+    Custom(CustomOp),
+}
+
+impl Op {
+    pub fn operator(&self) -> &'static dyn Operator {
+        operator(self)
+    }
+
+    pub fn is_unary(&self) -> bool {
+        self.operator().arity() == 1
+    }
+
+    // Written before the operand rather than after it
+    pub fn is_prefix(&self) -> bool {
+        self.operator().prefix()
+    }
 }
 
 // NOTE: Not using this currently - lowercase Op values used via clap
@@ -16,31 +47,27 @@ impl TryFrom<char> for Op {
     type Error = &'static str;
 
     fn try_from(value: char) -> Result<Self, Self::Error> {
-        match value {
-            '+' => Ok(Op::Add),
-            '-' => Ok(Op::Sub),
-            '*' => Ok(Op::Mul),
-            '/' => Ok(Op::Div),
-            '^' => Ok(Op::Exp),
-            '%' => Ok(Op::Mod),
-            _ => Err("Invalid op - only + - * / ^ are allowed"),
-        }
+        find(&value.to_string()).ok_or("Invalid op - not a registered operator symbol")
     }
 }
 
 impl Display for Op {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Op::Add => write!(f, "+"),
-            Op::Sub => write!(f, "-"),
-            Op::Mul => write!(f, "*"),
-            Op::Div => write!(f, "/"),
-            Op::Exp => write!(f, "^"),
-            Op::Mod => write!(f, "%"),
-        }
+        write!(f, "{}", self.operator().symbol())
     }
 }
 
+impl Serialize for Op {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.operator().name())
+    }
+}
+
+// Binding strength, as used by the parser
+pub fn precedence(op: &Op) -> u8 {
+    op.operator().precedence()
+}
+
 pub type OpsType = Vec<Op>;
 
 pub fn all_ops() -> Vec<Op> {
@@ -51,13 +78,54 @@ pub fn std_ops() -> Vec<Op> {
     [Op::Add, Op::Sub, Op::Mul, Op::Div].to_vec()
 }
 
-#[derive(Clone, Debug)]
-pub enum Expr {
-    Val(i32),
-    Expr(Op, Box<Expr>, Box<Expr>),
+// Subtrees are shared, so the solvers can build on a result without copying it
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Expr<N = i32> {
+    Val(N),
+    Expr(Op, Arc<Expr<N>>, Arc<Expr<N>>),
+    Unary(Op, Arc<Expr<N>>),
 }
 
-impl Expr {
+impl<N: Number> Expr<N> {
+    // Unary operators bind tighter than any binary one, and postfix tighter
+    // than prefix, so only these operands need brackets
+    fn unary_brak(op: &Op, a: &Self) -> bool {
+        match a {
+            // -(3) keeps a Neg apart from the number -3
+            Expr::Val(v) => v < &N::zero() || op == &Op::Neg,
+            Expr::Expr(..) => true,
+            Expr::Unary(o, _) => !op.is_prefix() && o.is_prefix(),
+        }
+    }
+
+    fn unary_fmt(
+        op: &Op,
+        a: &Self,
+        f: &mut std::fmt::Formatter<'_>,
+        fmt: fn(&Self, &mut std::fmt::Formatter<'_>) -> std::fmt::Result,
+    ) -> std::fmt::Result {
+        match op {
+            Op::Neg => write!(f, "-")?,
+            _ if op.is_prefix() => write!(f, "{} ", op)?,
+            _ => (),
+        }
+
+        if Self::unary_brak(op, a) {
+            write!(f, "(")?;
+            fmt(a, f)?;
+            write!(f, ")")?;
+        } else {
+            fmt(a, f)?;
+        }
+
+        if !op.is_prefix() {
+            write!(f, "{}", op)?;
+        }
+
+        Ok(())
+    }
+
     fn brak_fmt(e: &Self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match e {
             Expr::Val(v) => write!(f, "{}", v),
@@ -68,31 +136,75 @@ impl Expr {
                 Self::brak_fmt(b, f)?;
                 write!(f, ")")
             }
+            Expr::Unary(op, a) => Self::unary_fmt(op, a, f, |a, f| write!(f, "{}", a)),
+        }
+    }
+
+    // Only brackets needed for precedence - an operand of equal precedence on
+    // the side its operator doesn't associate with still needs them
+    fn min_brak_fmt(
+        e: &Self,
+        parent: Option<(&Op, bool)>,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match e {
+            Expr::Val(v) => write!(f, "{}", v),
+            Expr::Expr(op, a, b) => {
+                let brak = match parent {
+                    Some((p, is_right)) => {
+                        let (op, p) = (op.operator(), p.operator());
+
+                        op.precedence() < p.precedence()
+                            || (op.precedence() == p.precedence()
+                                && (op.assoc() != p.assoc()
+                                    || is_right == (p.assoc() == Assoc::Left)))
+                    }
+                    None => false,
+                };
+
+                if brak {
+                    write!(f, "(")?;
+                }
+                Self::min_brak_fmt(a, Some((op, false)), f)?;
+                write!(f, " {} ", op)?;
+                Self::min_brak_fmt(b, Some((op, true)), f)?;
+                if brak {
+                    write!(f, ")")?;
+                }
+
+                Ok(())
+            }
+            Expr::Unary(op, a) => Self::unary_fmt(op, a, f, |a, f| Self::min_brak_fmt(a, None, f)),
         }
     }
 
-    fn get_vals(e: &Expr, op: &Op) -> Vec<i32> {
-        let mut vals = Vec::<i32>::new();
+    pub fn pretty(&self) -> String {
+        Pretty(self).to_string()
+    }
+
+    fn get_vals(e: &Self, op: &Op) -> Vec<N> {
+        let mut vals = Vec::<N>::new();
 
         match e {
-            Expr::Val(n) => vals.push(*n),
+            Expr::Val(n) => vals.push(n.clone()),
             Expr::Expr(o, a, b) => {
                 if o == op {
-                    if let Expr::Val(n) = **a {
-                        vals.push(n)
+                    if let Expr::Val(n) = &**a {
+                        vals.push(n.clone())
                     }
-                    if let Expr::Val(n) = **b {
-                        vals.push(n)
+                    if let Expr::Val(n) = &**b {
+                        vals.push(n.clone())
                     }
                 }
             }
+            Expr::Unary(..) => (),
         }
 
         vals
     }
 
-    fn get_all_vals(e1: &Expr, e2: &Expr, op: &Op) -> Vec<i32> {
-        let mut vals = Vec::<i32>::new();
+    fn get_all_vals(e1: &Self, e2: &Self, op: &Op) -> Vec<N> {
+        let mut vals = Vec::<N>::new();
 
         vals.append(&mut Self::get_vals(e1, op));
         vals.append(&mut Self::get_vals(e2, op));
@@ -101,7 +213,7 @@ impl Expr {
         vals
     }
 
-    fn eq_vals(l1: &Expr, l2: &Expr, r1: &Expr, r2: &Expr, op: &Op) -> bool {
+    fn eq_vals(l1: &Self, l2: &Self, r1: &Self, r2: &Self, op: &Op) -> bool {
         let lvals = Self::get_all_vals(l1, l2, op);
         let rvals = Self::get_all_vals(r1, r2, op);
 
@@ -109,21 +221,30 @@ impl Expr {
     }
 }
 
-impl Display for Expr {
+impl<N: Number> Display for Expr<N> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Expr::Val(v) => write!(f, "{}", v),
             Expr::Expr(op, a, b) => {
-                Expr::brak_fmt(&*a, f)?;
+                Expr::brak_fmt(a, f)?;
                 write!(f, " {} ", op)?;
-                Expr::brak_fmt(&*b, f)
+                Expr::brak_fmt(b, f)
             }
+            Expr::Unary(..) => Expr::brak_fmt(self, f),
         }
     }
 }
 
+struct Pretty<'a, N>(&'a Expr<N>);
+
+impl<N: Number> Display for Pretty<'_, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Expr::min_brak_fmt(self.0, None, f)
+    }
+}
+
 // Expr equality for duplicate removal
-impl PartialEq for Expr {
+impl<N: Number> PartialEq for Expr<N> {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::Val(l), Self::Val(r)) => l == r,
@@ -145,46 +266,125 @@ impl PartialEq for Expr {
                     lo == ro && l1 == r1 && l2 == r2
                 }
             }
+            (Self::Unary(lo, l), Self::Unary(ro, r)) => lo == ro && l == r,
             _ => false,
         }
     }
 }
 
-pub fn valid(op: &Op, a: i32, b: i32) -> bool {
+// Commutative operations only one way round - removes reversed duplicates
+pub fn ordered<T: Ord>(op: &Op, a: &T, b: &T) -> bool {
+    !op.operator().commutative() || a <= b
+}
+
+pub fn valid<N: Number>(op: &Op, a: &N, b: &N) -> bool {
     // Optimised checks to remove redundant operations and reversed duplicates
-    return match op {
-        Op::Add => a <= b,
-        Op::Sub => a > b,
-        Op::Mul => a != 1 && b != 1 && a <= b && a.checked_mul(b).is_some(),
-        Op::Div => b > 1 && a % b == 0,
-        Op::Exp => a > 1 && b > 1 && a.checked_pow(b as u32).is_some(),
-        Op::Mod => a > 0 && b != 0,
-    };
+    ordered(op, a, b) && Rules::default().allows(op, a, b)
+}
+
+pub fn valid_unary<N: Number>(op: &Op, a: &N) -> bool {
+    Rules::default().apply_unary(op, a).is_ok()
 }
 
 const APPLY_FREELY: bool = true;
 const APPLY_OPTIMISED: bool = false;
 
-pub fn apply(op: &Op, a: &Expr, b: &Expr, freely: bool) -> Option<i32> {
-    if let Some(a) = sub_eval(a, freely) {
-        if let Some(b) = sub_eval(b, freely) {
-            if freely || valid(&op, a, b) {
-                return match op {
-                    Op::Add => Some(a + b),
-                    Op::Sub => Some(a - b),
-                    Op::Mul => Some(a * b),
-                    Op::Div => Some(a / b),
-                    Op::Exp => Some(a.pow(b as u32)),
-                    Op::Mod => Some(a % b),
-                };
-            }
-        }
+// Each error carries the step that failed, e.g. "7 / 2"
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EvalError {
+    #[error("{0} overflows")]
+    Overflow(String),
+    #[error("{0} divides by zero")]
+    DivByZero(String),
+    #[error("{0} does not divide exactly")]
+    NotExact(String),
+    #[error("{0} has a negative exponent")]
+    NegativeExponent(String),
+    #[error("{0} is not a perfect square")]
+    NotSquare(String),
+    #[error("{0} is undefined")]
+    Undefined(String),
+    #[error("{0} is not allowed by the rules")]
+    Rule(String),
+}
+
+pub fn step<T: Display>(op: &Op, a: T, b: T) -> String {
+    format!("{} {} {}", a, op, b)
+}
+
+pub fn unary_step<T: Display>(op: &Op, a: T) -> String {
+    match op {
+        Op::Neg => format!("-({})", a),
+        _ if op.is_prefix() => format!("{} {}", op, a),
+        _ => format!("{}{}", a, op),
+    }
+}
+
+// Checked arithmetic on two values, with no rule checks
+pub fn apply_vals<N: Number>(op: &Op, a: &N, b: &N) -> Result<N, EvalError> {
+    let operator = op.operator();
+
+    if operator.arity() != 2 {
+        return Err(EvalError::Rule(step(op, a, b)));
+    }
+
+    N::apply_op(operator, a, b)
+}
+
+// Checked unary operation on a value, with no rule checks
+pub fn apply_unary_vals<N: Number>(op: &Op, a: &N) -> Result<N, EvalError> {
+    let operator = op.operator();
+
+    if operator.arity() != 1 {
+        return Err(EvalError::Rule(unary_step(op, a)));
+    }
+
+    N::apply_op(operator, a, a)
+}
+
+pub fn apply_unary<N: Number>(op: &Op, a: &Expr<N>, freely: bool) -> Result<N, EvalError> {
+    let a = sub_eval(a, freely)?;
+
+    let res = apply_unary_vals(op, &a)?;
+
+    if freely || valid_unary(op, &a) {
+        Ok(res)
+    } else {
+        Err(EvalError::Rule(unary_step(op, a)))
+    }
+}
+
+// Concatenation can only join tiles, not the results of other operations
+pub fn concatenable<N>(op: &Op, a: &Expr<N>, b: &Expr<N>) -> bool {
+    !op.operator().literal_only() || (a.is_literal() && b.is_literal())
+}
+
+pub fn check_concat<N: Number>(op: &Op, a: &Expr<N>, b: &Expr<N>) -> Result<(), EvalError> {
+    if concatenable(op, a, b) {
+        Ok(())
+    } else {
+        let expr = Expr::new_expr(op.clone(), a.clone(), b.clone());
+
+        Err(EvalError::Rule(expr.to_string()))
     }
+}
 
-    None
+pub fn apply<N: Number>(op: &Op, a: &Expr<N>, b: &Expr<N>, freely: bool) -> Result<N, EvalError> {
+    check_concat(op, a, b)?;
+
+    let a = sub_eval(a, freely)?;
+    let b = sub_eval(b, freely)?;
+
+    let res = apply_vals(op, &a, &b)?;
+
+    if freely || valid(op, &a, &b) {
+        Ok(res)
+    } else {
+        Err(EvalError::Rule(step(op, a, b)))
+    }
 }
 
-pub fn sub_eval(expr: &Expr, freely: bool) -> Option<i32> {
+pub fn sub_eval<N: Number>(expr: &Expr<N>, freely: bool) -> Result<N, EvalError> {
     if freely {
         eval_freely(expr)
     } else {
@@ -192,27 +392,118 @@ pub fn sub_eval(expr: &Expr, freely: bool) -> Option<i32> {
     }
 }
 
-pub fn eval(expr: &Expr) -> Option<i32> {
+pub fn eval<N: Number>(expr: &Expr<N>) -> Result<N, EvalError> {
     match expr {
-        Expr::Val(v) => Some(*v),
+        Expr::Val(v) => Ok(v.clone()),
         Expr::Expr(op, a, b) => apply(op, a, b, APPLY_OPTIMISED),
+        Expr::Unary(op, a) => apply_unary(op, a, APPLY_OPTIMISED),
     }
 }
 
-pub fn eval_freely(expr: &Expr) -> Option<i32> {
+pub fn eval_freely<N: Number>(expr: &Expr<N>) -> Result<N, EvalError> {
     match expr {
-        Expr::Val(v) => Some(*v),
+        Expr::Val(v) => Ok(v.clone()),
         Expr::Expr(op, a, b) => apply(op, a, b, APPLY_FREELY),
+        Expr::Unary(op, a) => apply_unary(op, a, APPLY_FREELY),
+    }
+}
+
+// Evaluation under a rule set, operands may be in either order
+pub fn eval_with<N: Number>(expr: &Expr<N>, rules: &Rules) -> Result<N, EvalError> {
+    match expr {
+        Expr::Val(v) => Ok(v.clone()),
+        Expr::Expr(op, a, b) => {
+            check_concat(op, a, b)?;
+
+            rules.apply(op, &eval_with(a, rules)?, &eval_with(b, rules)?)
+        }
+        Expr::Unary(op, a) => rules.apply_unary(op, &eval_with(a, rules)?),
+    }
+}
+
+// A single calculation in a solution, as a player would write it down - b is
+// None for unary operations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step<T = i32> {
+    pub op: Op,
+    pub a: T,
+    pub b: Option<T>,
+    pub result: T,
+}
+
+impl<T: Display> Display for Step<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.b {
+            Some(b) => write!(f, "{} = {}", step(&self.op, &self.a, b), self.result),
+            None => write!(f, "{} = {}", unary_step(&self.op, &self.a), self.result),
+        }
+    }
+}
+
+fn add_steps(expr: &Expr, steps: &mut Vec<Step>) -> Option<i32> {
+    match expr {
+        Expr::Val(v) => Some(*v),
+        Expr::Expr(op, a, b) => {
+            let a = add_steps(a, steps)?;
+            let b = add_steps(b, steps)?;
+            let result = apply_vals(op, &a, &b).ok()?;
+
+            steps.push(Step {
+                op: op.clone(),
+                a,
+                b: Some(b),
+                result,
+            });
+
+            Some(result)
+        }
+        Expr::Unary(op, a) => {
+            let a = add_steps(a, steps)?;
+            let result = apply_unary_vals(op, &a).ok()?;
+
+            steps.push(Step {
+                op: op.clone(),
+                a,
+                b: None,
+                result,
+            });
+
+            Some(result)
+        }
     }
 }
 
-impl Expr {
-    pub fn new_val(val: i32) -> Expr {
+// Steps in evaluation order - stops at the first step that can't be evaluated
+pub fn steps(expr: &Expr) -> Vec<Step> {
+    let mut steps = Vec::<Step>::new();
+
+    add_steps(expr, &mut steps);
+
+    steps
+}
+
+impl<N> Expr<N> {
+    pub fn new_val(val: N) -> Self {
         Expr::Val(val)
     }
 
-    pub fn new_expr(op: Op, a: Expr, b: Expr) -> Expr {
-        Expr::Expr(op, Box::<Expr>::new(a), Box::<Expr>::new(b))
+    pub fn new_expr(op: Op, a: Self, b: Self) -> Self {
+        Expr::Expr(op, Arc::new(a), Arc::new(b))
+    }
+
+    pub fn new_unary(op: Op, a: Self) -> Self {
+        Expr::Unary(op, Arc::new(a))
+    }
+
+    // A tile, or tiles joined by concatenation
+    pub fn is_literal(&self) -> bool {
+        match self {
+            Expr::Val(_) => true,
+            Expr::Expr(op, a, b) if op.operator().literal_only() => {
+                a.is_literal() && b.is_literal()
+            }
+            _ => false,
+        }
     }
 }
 
@@ -241,7 +532,7 @@ mod tests {
         let result = eval(&expr);
         println!("expr: {} = {:?}", expr, result);
 
-        assert_eq!(result, Some(10));
+        assert_eq!(result, Ok(10));
     }
 
     #[test]
@@ -252,7 +543,7 @@ mod tests {
         let result = eval(&expr);
         println!("expr: {} = {:?}", expr, result);
 
-        assert_eq!(result, Some(25));
+        assert_eq!(result, Ok(25));
     }
 
     #[test]
@@ -271,7 +562,7 @@ mod tests {
         let result = eval(&expr);
         println!("expr: {} = {:?}", expr, result);
 
-        assert_eq!(result, Some(390));
+        assert_eq!(result, Ok(390));
     }
 
     #[test]
@@ -379,12 +670,145 @@ mod tests {
         assert!(!std::ptr::eq(&e1, &e2));
     }
 
+    #[test]
+    fn steps_nested() {
+        let expr = Expr::new_expr(
+            Op::Mul,
+            Expr::new_expr(Op::Add, Expr::new_val(100), Expr::new_val(4)),
+            Expr::new_val(6),
+        );
+
+        let res = steps(&expr)
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(res, vec!["100 + 4 = 104", "104 * 6 = 624"]);
+        assert!(steps(&Expr::new_val(6)).is_empty());
+    }
+
+    #[test]
+    fn pretty_min_brackets() {
+        // (25 + 50) * (75 - (100 / 4))
+        let expr = Expr::new_expr(
+            Op::Mul,
+            Expr::new_expr(Op::Add, Expr::new_val(25), Expr::new_val(50)),
+            Expr::new_expr(
+                Op::Sub,
+                Expr::new_val(75),
+                Expr::new_expr(Op::Div, Expr::new_val(100), Expr::new_val(4)),
+            ),
+        );
+
+        assert_eq!(expr.pretty(), "(25 + 50) * (75 - 100 / 4)");
+    }
+
+    #[test]
+    fn expr_json() {
+        let expr = Expr::new_expr(Op::Add, Expr::new_val(1), Expr::new_val(2));
+
+        let json = serde_json::to_string(&expr).unwrap();
+
+        assert_eq!(json, r#"{"expr":["add",{"val":1},{"val":2}]}"#);
+    }
+
+    #[test]
+    fn eval_errors() {
+        let eval_op =
+            |op, a, b| eval_freely(&Expr::new_expr(op, Expr::new_val(a), Expr::new_val(b)));
+
+        assert_eq!(
+            eval_op(Op::Add, i32::MAX, 1),
+            Err(EvalError::Overflow(format!("{} + 1", i32::MAX)))
+        );
+        assert_eq!(
+            eval_op(Op::Div, 7, 0),
+            Err(EvalError::DivByZero("7 / 0".to_string()))
+        );
+        assert_eq!(
+            eval_op(Op::Mod, 7, 0),
+            Err(EvalError::DivByZero("7 % 0".to_string()))
+        );
+        assert_eq!(
+            eval_op(Op::Div, 7, 2),
+            Err(EvalError::NotExact("7 / 2".to_string()))
+        );
+        assert_eq!(
+            eval_op(Op::Exp, 2, -1),
+            Err(EvalError::NegativeExponent("2 ^ -1".to_string()))
+        );
+
+        let expr = Expr::new_expr(Op::Sub, Expr::new_val(2), Expr::new_val(7));
+        assert_eq!(eval(&expr), Err(EvalError::Rule("2 - 7".to_string())));
+        assert_eq!(eval_freely(&expr), Ok(-5));
+    }
+
+    #[test]
+    fn unary_ops() {
+        let fact = |a| Expr::new_unary(Op::Fact, a);
+        let sqrt = |a| Expr::new_unary(Op::Sqrt, a);
+
+        let expr = fact(sqrt(Expr::new_expr(
+            Op::Add,
+            Expr::new_val(7),
+            Expr::new_val(9),
+        )));
+
+        assert_eq!(expr.to_string(), "(sqrt (7 + 9))!");
+        assert_eq!(eval(&expr), Ok(24));
+
+        let expr = Expr::new_expr(Op::Sub, fact(Expr::new_val(4)), sqrt(Expr::new_val(9)));
+
+        assert_eq!(expr.pretty(), "4! - sqrt 9");
+        assert_eq!(eval(&expr), Ok(21));
+
+        assert_eq!(
+            eval(&sqrt(Expr::new_val(8))),
+            Err(EvalError::NotSquare("sqrt 8".to_string()))
+        );
+        assert_eq!(
+            eval_freely(&fact(Expr::new_val(-3))),
+            Err(EvalError::Undefined("-3!".to_string()))
+        );
+        assert_eq!(
+            eval(&fact(Expr::new_val(13))),
+            Err(EvalError::Overflow("13!".to_string()))
+        );
+        assert_eq!(
+            eval(&Expr::new_unary(Op::Fact, Expr::new_val(13_i64))),
+            Ok(6227020800)
+        );
+        assert_eq!(
+            eval(&Expr::new_unary(Op::Neg, Expr::new_val(3))),
+            Err(EvalError::Rule("-(3)".to_string()))
+        );
+    }
+
+    #[test]
+    fn concatenation() {
+        let cat = |a, b| Expr::new_expr(Op::Cat, a, b);
+
+        let expr = cat(cat(Expr::new_val(1), Expr::new_val(0)), Expr::new_val(25));
+        assert_eq!(expr.to_string(), "(1 || 0) || 25");
+        assert_eq!(eval_freely(&expr), Ok(1025));
+
+        let sum = Expr::new_expr(Op::Add, Expr::new_val(1), Expr::new_val(2));
+        assert_eq!(
+            eval_freely(&cat(sum, Expr::new_val(3))),
+            Err(EvalError::Rule("(1 + 2) || 3".to_string()))
+        );
+        assert_eq!(
+            eval_freely(&cat(Expr::new_val(-1), Expr::new_val(2))),
+            Err(EvalError::Undefined("-1 || 2".to_string()))
+        );
+    }
+
     #[test]
     fn eval_exp() {
         let expr = Expr::new_expr(Op::Exp, Expr::new_val(4), Expr::new_val(5));
 
         let res = eval(&expr);
 
-        assert_eq!(Some(1024), res);
+        assert_eq!(Ok(1024), res);
     }
 }
@@ -0,0 +1,86 @@
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+use crate::expr::std_ops;
+use crate::rank::rank_solutions;
+use crate::rules::Rules;
+use crate::solver_v2::{solutions2_nearest, Result};
+
+// A full practice game - the pieces that don't need a terminal
+
+// Thinking time for each round
+pub const SECONDS: u64 = 30;
+
+// Time to declare an answer once thinking time is up
+pub const DECLARE: u64 = 5;
+
+// The first line received before time runs out. tick is called with the
+// seconds left at the start of each second. None if time runs out or there
+// are no more lines.
+pub fn answer_within(
+    lines: &Receiver<String>,
+    seconds: u64,
+    mut tick: impl FnMut(u64),
+) -> Option<String> {
+    for left in (1..=seconds).rev() {
+        tick(left);
+
+        match lines.recv_timeout(Duration::from_secs(1)) {
+            Ok(line) => return Some(line),
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => return None,
+        }
+    }
+
+    None
+}
+
+// The simplest solution, or the simplest of the closest when the target can't
+// be reached, along with its distance from the target
+pub fn best_answer(tiles: &[i32], target: i32) -> Option<(Result, u32)> {
+    // Exact solutions are the nearest, at distance 0
+    let ((solns, distance), _) = solutions2_nearest(tiles, target, &std_ops(), &Rules::official());
+
+    rank_solutions(&solns)
+        .into_iter()
+        .next()
+        .map(|(best, _)| (best, distance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn timed_answers() {
+        let (tx, rx) = channel();
+        let mut ticks = Vec::<u64>::new();
+
+        tx.send("25 * 4".to_string()).unwrap();
+        assert_eq!(
+            answer_within(&rx, SECONDS, |left| ticks.push(left)),
+            Some("25 * 4".to_string())
+        );
+        assert_eq!(ticks, vec![SECONDS]);
+
+        assert_eq!(answer_within(&rx, 0, |_| ()), None);
+
+        drop(tx);
+        assert_eq!(answer_within(&rx, SECONDS, |_| ()), None);
+    }
+
+    #[test]
+    fn best_answers() {
+        let (best, distance) = best_answer(&[25, 50, 75, 100, 3, 6], 952).unwrap();
+
+        assert_eq!(best.1, 952);
+        assert_eq!(distance, 0);
+
+        // (1 + 2) * 3 is as close as it gets
+        let (best, distance) = best_answer(&[1, 2, 3], 100).unwrap();
+
+        assert_eq!(best.1, 9);
+        assert_eq!(distance, 91);
+    }
+}
@@ -0,0 +1,91 @@
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use thiserror::Error;
+
+// Dealing a numbers round from the official 24 tile pool
+
+pub const LARGE: [i32; 4] = [25, 50, 75, 100];
+pub const TILES: usize = 6;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DealError {
+    #[error("{0} large tiles requested - at most {} allowed", LARGE.len())]
+    TooManyLarge(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deal {
+    pub tiles: Vec<i32>,
+    pub target: i32,
+}
+
+// Two of each number from 1 to 10
+pub fn small_pool() -> Vec<i32> {
+    (1..=10).flat_map(|n| [n, n]).collect()
+}
+
+pub fn deal_with<R: Rng>(large: usize, rng: &mut R) -> Result<Deal, DealError> {
+    if large > LARGE.len() {
+        return Err(DealError::TooManyLarge(large));
+    }
+
+    let mut tiles = LARGE
+        .choose_multiple(rng, large)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    tiles.extend(small_pool().choose_multiple(rng, TILES - large));
+
+    Ok(Deal {
+        tiles,
+        target: rng.gen_range(101..=999),
+    })
+}
+
+// Deals, letters and conundrums are reproducible for a given seed
+pub fn rng(seed: Option<u64>) -> ChaCha8Rng {
+    match seed {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+        None => ChaCha8Rng::from_entropy(),
+    }
+}
+
+pub fn deal(large: usize, seed: Option<u64>) -> Result<Deal, DealError> {
+    deal_with(large, &mut rng(seed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deal_rules() {
+        (0..=4).for_each(|large| {
+            let deal = deal(large, None).unwrap();
+
+            assert_eq!(deal.tiles.len(), TILES);
+            assert_eq!(
+                deal.tiles.iter().filter(|t| LARGE.contains(t)).count(),
+                large
+            );
+            assert!((101..=999).contains(&deal.target));
+
+            // No tile is drawn more often than it appears in the pool
+            deal.tiles.iter().for_each(|t| {
+                let drawn = deal.tiles.iter().filter(|d| d == &t).count();
+                assert!(drawn <= if LARGE.contains(t) { 1 } else { 2 });
+            });
+        });
+    }
+
+    #[test]
+    fn seeded_repeats() {
+        assert_eq!(deal(2, Some(42)), deal(2, Some(42)));
+    }
+
+    #[test]
+    fn too_many_large() {
+        assert_eq!(deal(5, Some(1)), Err(DealError::TooManyLarge(5)));
+    }
+}
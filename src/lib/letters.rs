@@ -0,0 +1,275 @@
+use std::path::Path;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use thiserror::Error;
+
+use crate::generate::rng;
+
+// Letters round - the longest words that can be made from nine letters
+//
+// Each word is indexed by how many times it uses each letter, so a word can be
+// made when no letter is needed more often than it was drawn.
+
+pub const LETTERS: usize = 9;
+
+// Letter frequencies in the vowel and consonant piles
+const VOWELS: [(char, usize); 5] = [('A', 15), ('E', 21), ('I', 13), ('O', 13), ('U', 5)];
+const CONSONANTS: [(char, usize); 21] = [
+    ('B', 2),
+    ('C', 3),
+    ('D', 6),
+    ('F', 2),
+    ('G', 3),
+    ('H', 2),
+    ('J', 1),
+    ('K', 1),
+    ('L', 5),
+    ('M', 4),
+    ('N', 8),
+    ('P', 4),
+    ('Q', 1),
+    ('R', 9),
+    ('S', 9),
+    ('T', 9),
+    ('V', 1),
+    ('W', 1),
+    ('X', 1),
+    ('Y', 1),
+    ('Z', 1),
+];
+
+pub type Counts = [u8; 26];
+
+#[derive(Debug, Error)]
+pub enum LettersError {
+    #[error("can't read dictionary: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("'{0}' must be 1 to {LETTERS} letters from A to Z")]
+    Letters(String),
+    #[error("'{0}' must be exactly {LETTERS} letters from A to Z")]
+    Conundrum(String),
+    #[error("{0} vowels requested - a deal has 3 to 5")]
+    Vowels(usize),
+}
+
+// None when the word has anything but the letters A to Z
+pub fn counts(word: &str) -> Option<Counts> {
+    let mut counts: Counts = [0; 26];
+
+    for c in word.chars() {
+        if !c.is_ascii_alphabetic() {
+            return None;
+        }
+
+        // Saturates, as no more than LETTERS of any letter can be dealt
+        let count = &mut counts[(c.to_ascii_uppercase() as u8 - b'A') as usize];
+        *count = count.saturating_add(1);
+    }
+
+    Some(counts)
+}
+
+// Every letter of the word is available
+pub fn fits(word: &Counts, letters: &Counts) -> bool {
+    word.iter().zip(letters).all(|(w, l)| w <= l)
+}
+
+pub fn parse_letters(letters: &str) -> Result<Counts, LettersError> {
+    if !(1..=LETTERS).contains(&letters.len()) {
+        return Err(LettersError::Letters(letters.to_string()));
+    }
+
+    counts(letters).ok_or_else(|| LettersError::Letters(letters.to_string()))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    words: Vec<(String, Counts)>,
+}
+
+impl Dictionary {
+    // Words are upper cased. Anything that isn't purely letters, or is too
+    // long to ever be made, is left out.
+    pub fn from_words<'a>(words: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut words = words
+            .into_iter()
+            .map(str::trim)
+            .filter(|w| (1..=LETTERS).contains(&w.len()))
+            .filter_map(|w| counts(w).map(|c| (w.to_ascii_uppercase(), c)))
+            .collect::<Vec<_>>();
+
+        words.sort();
+        words.dedup();
+
+        Dictionary { words }
+    }
+
+    // One word per line
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LettersError> {
+        let text = std::fs::read_to_string(path)?;
+
+        Ok(Self::from_words(text.lines()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.words.iter().map(|(w, _)| w.as_str())
+    }
+
+    // Words are kept sorted
+    pub fn contains(&self, word: &str) -> bool {
+        self.words
+            .binary_search_by(|(w, _)| w.as_str().cmp(word))
+            .is_ok()
+    }
+
+    // Points for a player's word - 0 if it isn't a word or can't be made
+    pub fn score(&self, letters: &str, word: &str) -> usize {
+        let word = word.trim().to_ascii_uppercase();
+
+        match (counts(&word), parse_letters(letters)) {
+            (Some(w), Ok(l)) if fits(&w, &l) && self.contains(&word) => points(&word),
+            _ => 0,
+        }
+    }
+
+    // Every word that can be made, longest first then alphabetical
+    pub fn solutions(&self, letters: &str) -> Result<Vec<&str>, LettersError> {
+        let letters = parse_letters(letters)?;
+
+        let mut found = self
+            .words
+            .iter()
+            .filter(|(_, c)| fits(c, &letters))
+            .map(|(w, _)| w.as_str())
+            .collect::<Vec<_>>();
+
+        found.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+
+        Ok(found)
+    }
+
+    // Only the words of the greatest length that can be made
+    pub fn longest(&self, letters: &str) -> Result<Vec<&str>, LettersError> {
+        let found = self.solutions(letters)?;
+        let best = found.first().map_or(0, |w| w.len());
+
+        Ok(found.into_iter().take_while(|w| w.len() == best).collect())
+    }
+}
+
+fn pile(freqs: &[(char, usize)]) -> Vec<char> {
+    freqs
+        .iter()
+        .flat_map(|(c, n)| std::iter::repeat_n(*c, *n))
+        .collect()
+}
+
+pub fn deal_letters_with<R: Rng>(vowels: usize, rng: &mut R) -> Result<String, LettersError> {
+    if !(3..=5).contains(&vowels) {
+        return Err(LettersError::Vowels(vowels));
+    }
+
+    let mut letters = pile(&VOWELS)
+        .choose_multiple(rng, vowels)
+        .cloned()
+        .collect::<String>();
+
+    letters.extend(pile(&CONSONANTS).choose_multiple(rng, LETTERS - vowels));
+
+    Ok(letters)
+}
+
+pub fn deal_letters(vowels: usize, seed: Option<u64>) -> Result<String, LettersError> {
+    deal_letters_with(vowels, &mut rng(seed))
+}
+
+// Official scoring - a point a letter, doubled for all nine
+pub fn points(word: &str) -> usize {
+    if word.len() == LETTERS {
+        2 * LETTERS
+    } else {
+        word.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dict() -> Dictionary {
+        Dictionary::from_words(["tea", "eat", "seat", "teases", "Paris", "it's", "sat", "a"])
+    }
+
+    #[test]
+    fn letter_multiplicity() {
+        let dict = dict();
+
+        assert_eq!(dict.len(), 7);
+        assert_eq!(dict.longest("SEATXQ").unwrap(), vec!["SEAT"]);
+        // TEASES needs two Es and two Ss
+        assert_eq!(dict.longest("TEASESQ").unwrap(), vec!["TEASES"]);
+        assert_eq!(dict.longest("TEASQ").unwrap(), vec!["SEAT"]);
+        assert_eq!(dict.solutions("TEA").unwrap(), vec!["EAT", "TEA", "A"]);
+    }
+
+    #[test]
+    fn bad_letters() {
+        let dict = dict();
+
+        assert!(matches!(
+            dict.longest("ABCDEFGHIJ"),
+            Err(LettersError::Letters(_))
+        ));
+        assert!(matches!(dict.longest("AB1"), Err(LettersError::Letters(_))));
+        assert!(dict.longest("QQQ").unwrap().is_empty());
+
+        let long = "A".repeat(300);
+
+        assert!(matches!(dict.longest(&long), Err(LettersError::Letters(_))));
+        assert_eq!(counts(&long).unwrap()[0], u8::MAX);
+        assert_eq!(dict.score("ABCDEFGHI", &long), 0);
+    }
+
+    #[test]
+    fn scoring() {
+        assert_eq!(points("SEAT"), 4);
+        assert_eq!(points("COUNTDOWN"), 18);
+
+        let dict = dict();
+
+        assert_eq!(dict.score("SEATXQ", "seat"), 4);
+        assert_eq!(dict.score("SEATXQ", "teases"), 0);
+        assert_eq!(dict.score("SEATXQ", "taes"), 0);
+    }
+
+    #[test]
+    fn deal_rules() {
+        (3..=5).for_each(|vowels| {
+            let letters = deal_letters(vowels, None).unwrap();
+
+            assert_eq!(letters.len(), LETTERS);
+            assert_eq!(
+                letters.chars().filter(|c| "AEIOU".contains(*c)).count(),
+                vowels
+            );
+        });
+
+        assert_eq!(
+            deal_letters(4, Some(42)).unwrap(),
+            deal_letters(4, Some(42)).unwrap()
+        );
+        assert!(matches!(
+            deal_letters(6, None),
+            Err(LettersError::Vowels(6))
+        ));
+    }
+}
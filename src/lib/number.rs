@@ -0,0 +1,182 @@
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+
+use num_bigint::{BigInt, BigUint};
+use num_integer::Roots;
+use num_traits::{One, Pow, Signed, ToPrimitive, Zero};
+
+use crate::expr::EvalError;
+use crate::operator::Operator;
+
+// Integer types expressions can be evaluated over
+//
+// All arithmetic is checked and gives None when the result can't be held.
+// Arbitrary precision values are capped at MAX_BITS so a chain of exponents
+// can't exhaust memory.
+
+pub const MAX_BITS: u64 = 1024;
+
+pub trait Number:
+    Clone + Debug + Display + Ord + Hash + Send + Sync + From<i32> + Zero + One
+{
+    // Unsigned, so the distance between any two values fits
+    type Distance: Clone + Debug + Display + Ord + Send + Sync + Zero;
+
+    fn checked_add(&self, b: &Self) -> Option<Self>;
+    fn checked_sub(&self, b: &Self) -> Option<Self>;
+    fn checked_mul(&self, b: &Self) -> Option<Self>;
+    fn checked_div(&self, b: &Self) -> Option<Self>;
+    fn checked_rem(&self, b: &Self) -> Option<Self>;
+    fn checked_exp(&self, b: &Self) -> Option<Self>;
+    // Rounded down, None when negative
+    fn checked_sqrt(&self) -> Option<Self>;
+    fn to_i64(&self) -> Option<i64>;
+    fn from_i64(val: i64) -> Option<Self>;
+    fn abs_diff(&self, b: &Self) -> Self::Distance;
+    // Picks the operator's implementation for this type
+    fn apply_op(op: &dyn Operator, a: &Self, b: &Self) -> Result<Self, EvalError>;
+}
+
+macro_rules! primitive_number {
+    ($t:ty, $distance:ty, $apply:ident) => {
+        impl Number for $t {
+            type Distance = $distance;
+
+            fn checked_add(&self, b: &Self) -> Option<Self> {
+                <$t>::checked_add(*self, *b)
+            }
+
+            fn checked_sub(&self, b: &Self) -> Option<Self> {
+                <$t>::checked_sub(*self, *b)
+            }
+
+            fn checked_mul(&self, b: &Self) -> Option<Self> {
+                <$t>::checked_mul(*self, *b)
+            }
+
+            fn checked_div(&self, b: &Self) -> Option<Self> {
+                <$t>::checked_div(*self, *b)
+            }
+
+            fn checked_rem(&self, b: &Self) -> Option<Self> {
+                <$t>::checked_rem(*self, *b)
+            }
+
+            fn checked_exp(&self, b: &Self) -> Option<Self> {
+                <$t>::checked_pow(*self, u32::try_from(*b).ok()?)
+            }
+
+            fn checked_sqrt(&self) -> Option<Self> {
+                (*self >= 0).then(|| Roots::sqrt(self))
+            }
+
+            fn to_i64(&self) -> Option<i64> {
+                i64::try_from(*self).ok()
+            }
+
+            fn from_i64(val: i64) -> Option<Self> {
+                <$t>::try_from(val).ok()
+            }
+
+            fn abs_diff(&self, b: &Self) -> Self::Distance {
+                <$t>::abs_diff(*self, *b)
+            }
+
+            fn apply_op(op: &dyn Operator, a: &Self, b: &Self) -> Result<Self, EvalError> {
+                op.$apply(*a, *b)
+            }
+        }
+    };
+}
+
+primitive_number!(i32, u32, apply_i32);
+primitive_number!(i64, u64, apply);
+
+impl Number for BigInt {
+    type Distance = BigUint;
+
+    fn checked_add(&self, b: &Self) -> Option<Self> {
+        Some(self + b)
+    }
+
+    fn checked_sub(&self, b: &Self) -> Option<Self> {
+        Some(self - b)
+    }
+
+    fn checked_mul(&self, b: &Self) -> Option<Self> {
+        if self.bits() + b.bits() > MAX_BITS {
+            None
+        } else {
+            Some(self * b)
+        }
+    }
+
+    fn checked_div(&self, b: &Self) -> Option<Self> {
+        if b.is_zero() {
+            None
+        } else {
+            Some(self / b)
+        }
+    }
+
+    fn checked_rem(&self, b: &Self) -> Option<Self> {
+        if b.is_zero() {
+            None
+        } else {
+            Some(self % b)
+        }
+    }
+
+    fn checked_exp(&self, b: &Self) -> Option<Self> {
+        let exp = b.to_u32()?;
+
+        if self.bits().saturating_mul(exp as u64) > MAX_BITS {
+            None
+        } else {
+            Some(Pow::pow(self, exp))
+        }
+    }
+
+    fn checked_sqrt(&self) -> Option<Self> {
+        (!self.is_negative()).then(|| Roots::sqrt(self))
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        ToPrimitive::to_i64(self)
+    }
+
+    fn from_i64(val: i64) -> Option<Self> {
+        Some(BigInt::from(val))
+    }
+
+    fn abs_diff(&self, b: &Self) -> Self::Distance {
+        (self - b).into_parts().1
+    }
+
+    fn apply_op(op: &dyn Operator, a: &Self, b: &Self) -> Result<Self, EvalError> {
+        op.apply_big(a, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn primitive_overflow() {
+        assert_eq!(Number::checked_exp(&2_i32, &31), None);
+        assert_eq!(Number::checked_exp(&2_i64, &31), Some(1 << 31));
+        assert_eq!(Number::checked_exp(&2_i64, &-1), None);
+    }
+
+    #[test]
+    fn big_capped() {
+        let two = BigInt::from(2);
+
+        assert_eq!(
+            two.checked_exp(&BigInt::from(100)),
+            Some(BigInt::from(1_u128 << 100))
+        );
+        assert_eq!(two.checked_exp(&BigInt::from(2000)), None);
+    }
+}
@@ -0,0 +1,695 @@
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::sync::RwLock;
+
+use num_bigint::BigInt;
+use num_traits::{checked_pow, CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, One, Zero};
+use thiserror::Error;
+
+use crate::canon::Group;
+use crate::expr::{step, unary_step, EvalError, Op};
+use crate::number::Number;
+use crate::rational::Rational;
+use crate::rules::Rules;
+
+// Operators - the built in ones and any registered from outside the crate
+//
+// Every Op is backed by an Operator, which the parser, printer, rules and
+// solvers use for symbols, precedence, checks and evaluation. Registering
+// returns an Op::Custom that can be used anywhere a built in Op can. Custom
+// operators work on i64 values, so any Number type can use them while values
+// fit.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assoc {
+    Left,
+    Right,
+}
+
+// How an operand compares with the values the rules care about, so the
+// checks work the same for any number type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Operand {
+    pub sign: Ordering,
+    pub one: Ordering,
+    pub two: Ordering,
+}
+
+impl Operand {
+    pub fn of<T: Ord + Zero + One>(val: &T) -> Self {
+        Operand {
+            sign: val.cmp(&T::zero()),
+            one: val.cmp(&T::one()),
+            two: val.cmp(&(T::one() + T::one())),
+        }
+    }
+}
+
+pub trait Operator: Send + Sync {
+    // Used for lookup, e.g. "add"
+    fn name(&self) -> &str;
+    // Written between the operands, e.g. "+"
+    fn symbol(&self) -> &str;
+    fn arity(&self) -> usize {
+        2
+    }
+    // Higher binds tighter - add and sub are 1, mul, div, exp and mod are 2,
+    // cat and the unary ops are 3
+    fn precedence(&self) -> u8;
+    fn assoc(&self) -> Assoc {
+        Assoc::Left
+    }
+    // Commutative operators are only tried one way round by the solvers
+    fn commutative(&self) -> bool {
+        false
+    }
+    // Unary operators written before their operand
+    fn prefix(&self) -> bool {
+        false
+    }
+    // Operands can only be tiles, never the results of other operations
+    fn literal_only(&self) -> bool {
+        false
+    }
+    // Sum or product that operands can be regrouped within, and whether the
+    // right hand operand is inverted - see canon
+    fn group(&self) -> Option<(Group, bool)> {
+        None
+    }
+    // How hard a step is for a player, used to rank solutions
+    fn cost(&self) -> u32 {
+        5
+    }
+    // Whether the rules allow a step on these operands, before it's calculated.
+    // order compares a with b. Unary operators get their operand as both.
+    fn allows(&self, _rules: &Rules, _a: Operand, _b: Operand, _order: Ordering) -> bool {
+        true
+    }
+    // Unary operators get their operand as both a and b
+    fn apply(&self, a: i64, b: i64) -> Result<i64, EvalError>;
+    // Other number types go through apply unless the operator handles them
+    fn apply_i32(&self, a: i32, b: i32) -> Result<i32, EvalError> {
+        through_i64(self, &a, &b)
+    }
+    fn apply_big(&self, a: &BigInt, b: &BigInt) -> Result<BigInt, EvalError> {
+        through_i64(self, a, b)
+    }
+    fn apply_rational(&self, a: Rational, b: Rational) -> Result<Rational, EvalError> {
+        if a.is_integer() && b.is_integer() {
+            self.apply(a.to_integer(), b.to_integer())
+                .map(Rational::from_integer)
+        } else {
+            Err(EvalError::Rule(symbol_step(self, a, b)))
+        }
+    }
+}
+
+fn symbol_step<O: Operator + ?Sized, T: Display>(operator: &O, a: T, b: T) -> String {
+    format!("{} {} {}", a, operator.symbol(), b)
+}
+
+fn through_i64<O: Operator + ?Sized, N: Number>(
+    operator: &O,
+    a: &N,
+    b: &N,
+) -> Result<N, EvalError> {
+    let overflow = || EvalError::Overflow(symbol_step(operator, a, b));
+    let x = a.to_i64().ok_or_else(overflow)?;
+    let y = b.to_i64().ok_or_else(overflow)?;
+
+    operator
+        .apply(x, y)
+        .and_then(|res| N::from_i64(res).ok_or_else(overflow))
+}
+
+fn overflow<T: Display>(op: &Op, a: T, b: T) -> EvalError {
+    EvalError::Overflow(step(op, a, b))
+}
+
+fn add<N: Number>(op: &Op, a: &N, b: &N) -> Result<N, EvalError> {
+    a.checked_add(b).ok_or_else(|| overflow(op, a, b))
+}
+
+fn sub<N: Number>(op: &Op, a: &N, b: &N) -> Result<N, EvalError> {
+    a.checked_sub(b).ok_or_else(|| overflow(op, a, b))
+}
+
+fn mul<N: Number>(op: &Op, a: &N, b: &N) -> Result<N, EvalError> {
+    a.checked_mul(b).ok_or_else(|| overflow(op, a, b))
+}
+
+fn div<N: Number>(op: &Op, a: &N, b: &N) -> Result<N, EvalError> {
+    if b.is_zero() {
+        Err(EvalError::DivByZero(step(op, a, b)))
+    } else if a.checked_rem(b).is_some_and(|r| !r.is_zero()) {
+        Err(EvalError::NotExact(step(op, a, b)))
+    } else {
+        a.checked_div(b).ok_or_else(|| overflow(op, a, b))
+    }
+}
+
+fn exp<N: Number>(op: &Op, a: &N, b: &N) -> Result<N, EvalError> {
+    if b < &N::zero() {
+        Err(EvalError::NegativeExponent(step(op, a, b)))
+    } else {
+        a.checked_exp(b).ok_or_else(|| overflow(op, a, b))
+    }
+}
+
+fn rem<N: Number>(op: &Op, a: &N, b: &N) -> Result<N, EvalError> {
+    if b.is_zero() {
+        Err(EvalError::DivByZero(step(op, a, b)))
+    } else {
+        a.checked_rem(b).ok_or_else(|| overflow(op, a, b))
+    }
+}
+
+fn cat<N: Number>(op: &Op, a: &N, b: &N) -> Result<N, EvalError> {
+    if a < &N::zero() || b < &N::zero() {
+        return Err(EvalError::Undefined(step(op, a, b)));
+    }
+
+    let overflow = || overflow(op, a, b);
+    let ten = N::from(10);
+    let mut shift = ten.clone();
+    let mut rest = b.checked_div(&ten).ok_or_else(overflow)?;
+
+    while !rest.is_zero() {
+        shift = shift.checked_mul(&ten).ok_or_else(overflow)?;
+        rest = rest.checked_div(&ten).ok_or_else(overflow)?;
+    }
+
+    a.checked_mul(&shift)
+        .and_then(|v| v.checked_add(b))
+        .ok_or_else(overflow)
+}
+
+// Largest factorial operand - 20! is the largest that fits an i64
+pub const MAX_FACT: i32 = 20;
+
+fn fact<N: Number>(op: &Op, a: &N) -> Result<N, EvalError> {
+    let overflow = || EvalError::Overflow(unary_step(op, a));
+
+    if a < &N::zero() {
+        return Err(EvalError::Undefined(unary_step(op, a)));
+    }
+
+    if a > &N::from(MAX_FACT) {
+        return Err(overflow());
+    }
+
+    let mut res = N::one();
+    let mut i = N::one();
+
+    while &i < a {
+        i = i.checked_add(&N::one()).ok_or_else(overflow)?;
+        res = res.checked_mul(&i).ok_or_else(overflow)?;
+    }
+
+    Ok(res)
+}
+
+fn sqrt<N: Number>(op: &Op, a: &N) -> Result<N, EvalError> {
+    if a < &N::zero() {
+        return Err(EvalError::Undefined(unary_step(op, a)));
+    }
+
+    let root = a
+        .checked_sqrt()
+        .ok_or_else(|| EvalError::Overflow(unary_step(op, a)))?;
+
+    if root.checked_mul(&root).as_ref() == Some(a) {
+        Ok(root)
+    } else {
+        Err(EvalError::NotSquare(unary_step(op, a)))
+    }
+}
+
+fn neg<N: Number>(op: &Op, a: &N) -> Result<N, EvalError> {
+    N::zero()
+        .checked_sub(a)
+        .ok_or_else(|| EvalError::Overflow(unary_step(op, a)))
+}
+
+macro_rules! builtin {
+    ($name:ident, $op:expr, $text:literal, $symbol:literal, $precedence:literal, $apply:ident { $($extra:tt)* }) => {
+        pub struct $name;
+
+        impl Operator for $name {
+            fn name(&self) -> &str {
+                $text
+            }
+
+            fn symbol(&self) -> &str {
+                $symbol
+            }
+
+            fn precedence(&self) -> u8 {
+                $precedence
+            }
+
+            fn apply(&self, a: i64, b: i64) -> Result<i64, EvalError> {
+                $apply(&$op, &a, &b)
+            }
+
+            fn apply_i32(&self, a: i32, b: i32) -> Result<i32, EvalError> {
+                $apply(&$op, &a, &b)
+            }
+
+            fn apply_big(&self, a: &BigInt, b: &BigInt) -> Result<BigInt, EvalError> {
+                $apply(&$op, a, b)
+            }
+
+            $($extra)*
+        }
+    };
+}
+
+builtin!(Add, Op::Add, "add", "+", 1, add {
+    fn commutative(&self) -> bool {
+        true
+    }
+
+    fn group(&self) -> Option<(Group, bool)> {
+        Some((Group::Sum, false))
+    }
+
+    fn cost(&self) -> u32 {
+        1
+    }
+
+    fn apply_rational(&self, a: Rational, b: Rational) -> Result<Rational, EvalError> {
+        a.checked_add(&b).ok_or_else(|| overflow(&Op::Add, a, b))
+    }
+});
+
+builtin!(Sub, Op::Sub, "sub", "-", 1, sub {
+    fn group(&self) -> Option<(Group, bool)> {
+        Some((Group::Sum, true))
+    }
+
+    fn cost(&self) -> u32 {
+        2
+    }
+
+    fn allows(&self, rules: &Rules, _a: Operand, _b: Operand, order: Ordering) -> bool {
+        match order {
+            Ordering::Greater => true,
+            Ordering::Equal => rules.allow_zero && rules.allow_cancel,
+            Ordering::Less => rules.allow_negatives,
+        }
+    }
+
+    fn apply_rational(&self, a: Rational, b: Rational) -> Result<Rational, EvalError> {
+        a.checked_sub(&b).ok_or_else(|| overflow(&Op::Sub, a, b))
+    }
+});
+
+builtin!(Mul, Op::Mul, "mul", "*", 2, mul {
+    fn commutative(&self) -> bool {
+        true
+    }
+
+    fn group(&self) -> Option<(Group, bool)> {
+        Some((Group::Product, false))
+    }
+
+    fn cost(&self) -> u32 {
+        3
+    }
+
+    fn allows(&self, rules: &Rules, a: Operand, b: Operand, _order: Ordering) -> bool {
+        rules.allow_identity || (a.one != Ordering::Equal && b.one != Ordering::Equal)
+    }
+
+    fn apply_rational(&self, a: Rational, b: Rational) -> Result<Rational, EvalError> {
+        a.checked_mul(&b).ok_or_else(|| overflow(&Op::Mul, a, b))
+    }
+});
+
+builtin!(Div, Op::Div, "div", "/", 2, div {
+    fn group(&self) -> Option<(Group, bool)> {
+        Some((Group::Product, true))
+    }
+
+    fn cost(&self) -> u32 {
+        4
+    }
+
+    fn allows(&self, rules: &Rules, _a: Operand, b: Operand, _order: Ordering) -> bool {
+        (rules.allow_identity || b.one != Ordering::Equal)
+            && (rules.allow_negatives || b.sign == Ordering::Greater)
+    }
+
+    // Division only has to be exact for integers
+    fn apply_rational(&self, a: Rational, b: Rational) -> Result<Rational, EvalError> {
+        if b.is_zero() {
+            Err(EvalError::DivByZero(step(&Op::Div, a, b)))
+        } else {
+            a.checked_div(&b).ok_or_else(|| overflow(&Op::Div, a, b))
+        }
+    }
+});
+
+builtin!(Exp, Op::Exp, "exp", "^", 2, exp {
+    fn allows(&self, rules: &Rules, a: Operand, b: Operand, _order: Ordering) -> bool {
+        rules.allow_identity || (a.one == Ordering::Greater && b.one == Ordering::Greater)
+    }
+
+    // Negative exponents give fractions
+    fn apply_rational(&self, a: Rational, b: Rational) -> Result<Rational, EvalError> {
+        let overflow = || overflow(&Op::Exp, a, b);
+
+        if !b.is_integer() {
+            return Err(EvalError::Rule(step(&Op::Exp, a, b)));
+        }
+
+        let exp = usize::try_from(b.to_integer().unsigned_abs()).map_err(|_| overflow())?;
+
+        if b < Rational::zero() {
+            if a.is_zero() {
+                return Err(EvalError::DivByZero(step(&Op::Exp, a, b)));
+            }
+            // recip can't negate an i64::MIN numerator
+            Rational::one()
+                .checked_div(&a)
+                .and_then(|recip| checked_pow(recip, exp))
+                .ok_or_else(overflow)
+        } else {
+            checked_pow(a, exp).ok_or_else(overflow)
+        }
+    }
+});
+
+builtin!(Mod, Op::Mod, "mod", "%", 2, rem {
+    fn allows(&self, rules: &Rules, a: Operand, _b: Operand, _order: Ordering) -> bool {
+        rules.allow_negatives || a.sign == Ordering::Greater
+    }
+});
+
+// Digit concatenation - 1 || 2 is 12
+builtin!(Cat, Op::Cat, "cat", "||", 3, cat {
+    fn literal_only(&self) -> bool {
+        true
+    }
+
+    fn cost(&self) -> u32 {
+        1
+    }
+});
+
+macro_rules! builtin_unary {
+    ($name:ident, $op:expr, $text:literal, $symbol:literal, $apply:ident { $($extra:tt)* }) => {
+        pub struct $name;
+
+        impl Operator for $name {
+            fn name(&self) -> &str {
+                $text
+            }
+
+            fn symbol(&self) -> &str {
+                $symbol
+            }
+
+            fn arity(&self) -> usize {
+                1
+            }
+
+            // Binds tighter than any binary operator
+            fn precedence(&self) -> u8 {
+                3
+            }
+
+            fn apply(&self, a: i64, _b: i64) -> Result<i64, EvalError> {
+                $apply(&$op, &a)
+            }
+
+            fn apply_i32(&self, a: i32, _b: i32) -> Result<i32, EvalError> {
+                $apply(&$op, &a)
+            }
+
+            fn apply_big(&self, a: &BigInt, _b: &BigInt) -> Result<BigInt, EvalError> {
+                $apply(&$op, a)
+            }
+
+            $($extra)*
+        }
+    };
+}
+
+builtin_unary!(Fact, Op::Fact, "fact", "!", fact {
+    // 1! and 2! are identities
+    fn allows(&self, rules: &Rules, a: Operand, _b: Operand, _order: Ordering) -> bool {
+        rules.allow_identity || a.two == Ordering::Greater
+    }
+
+    fn apply_rational(&self, a: Rational, _b: Rational) -> Result<Rational, EvalError> {
+        if a.is_integer() {
+            fact(&Op::Fact, &a.to_integer()).map(Rational::from_integer)
+        } else {
+            Err(EvalError::Rule(unary_step(&Op::Fact, a)))
+        }
+    }
+});
+
+builtin_unary!(Sqrt, Op::Sqrt, "sqrt", "sqrt", sqrt {
+    fn prefix(&self) -> bool {
+        true
+    }
+
+    fn allows(&self, rules: &Rules, a: Operand, _b: Operand, _order: Ordering) -> bool {
+        rules.allow_identity || a.one == Ordering::Greater
+    }
+
+    // A fraction's square root is exact when its numerator and denominator
+    // are both perfect squares
+    fn apply_rational(&self, a: Rational, _b: Rational) -> Result<Rational, EvalError> {
+        match (sqrt(&Op::Sqrt, a.numer()), sqrt(&Op::Sqrt, a.denom())) {
+            (Ok(n), Ok(d)) => Ok(Rational::new(n, d)),
+            (Err(EvalError::Undefined(_)), _) => {
+                Err(EvalError::Undefined(unary_step(&Op::Sqrt, a)))
+            }
+            _ => Err(EvalError::NotSquare(unary_step(&Op::Sqrt, a))),
+        }
+    }
+});
+
+builtin_unary!(Neg, Op::Neg, "neg", "-", neg {
+    fn prefix(&self) -> bool {
+        true
+    }
+
+    fn cost(&self) -> u32 {
+        2
+    }
+
+    fn apply_rational(&self, a: Rational, _b: Rational) -> Result<Rational, EvalError> {
+        Rational::zero()
+            .checked_sub(&a)
+            .ok_or_else(|| EvalError::Overflow(unary_step(&Op::Neg, a)))
+    }
+});
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum OperatorError {
+    #[error("'{0}' is already used by another operator")]
+    Duplicate(String),
+    #[error("'{0}' can't be used as an operator symbol")]
+    Symbol(String),
+    #[error("only binary operators can be registered, not arity {0}")]
+    Arity(usize),
+}
+
+// A registered operator - only made by register, so it always refers to one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CustomOp(usize);
+
+lazy_static::lazy_static! {
+    // Registered operators live for the rest of the program, so they can be
+    // handed out without holding the lock
+    static ref REGISTRY: RwLock<Vec<&'static dyn Operator>> = RwLock::new(Vec::new());
+}
+
+const BUILTIN: [Op; 10] = [
+    Op::Add,
+    Op::Sub,
+    Op::Mul,
+    Op::Div,
+    Op::Exp,
+    Op::Mod,
+    Op::Cat,
+    Op::Fact,
+    Op::Sqrt,
+    Op::Neg,
+];
+
+// Custom symbols must match bin_op in expr.pest
+pub fn symbol_char(c: char) -> bool {
+    c.is_ascii_alphabetic() || "&|~@#$<>?=:_".contains(c)
+}
+
+pub fn operator(op: &Op) -> &'static dyn Operator {
+    match op {
+        Op::Add => &Add,
+        Op::Sub => &Sub,
+        Op::Mul => &Mul,
+        Op::Div => &Div,
+        Op::Exp => &Exp,
+        Op::Mod => &Mod,
+        Op::Cat => &Cat,
+        Op::Fact => &Fact,
+        Op::Sqrt => &Sqrt,
+        Op::Neg => &Neg,
+        Op::Custom(CustomOp(i)) => REGISTRY.read().unwrap()[*i],
+    }
+}
+
+fn matches(operator: &dyn Operator, text: &str) -> bool {
+    operator.name() == text || operator.symbol() == text
+}
+
+// Registering the same name and symbol again gives back the same Op
+pub fn register(operator: impl Operator + 'static) -> Result<Op, OperatorError> {
+    if operator.arity() != 2 {
+        return Err(OperatorError::Arity(operator.arity()));
+    }
+
+    let symbol = operator.symbol().to_string();
+    let mut registry = REGISTRY.write().unwrap();
+
+    let same = |o: &dyn Operator| o.name() == operator.name() && o.symbol() == symbol;
+
+    if let Some(op) = BUILTIN.iter().find(|op| same(op.operator())) {
+        return Ok(op.clone());
+    }
+
+    if let Some(i) = registry.iter().position(|o| same(*o)) {
+        return Ok(Op::Custom(CustomOp(i)));
+    }
+
+    if symbol.is_empty() || !symbol.chars().all(symbol_char) {
+        return Err(OperatorError::Symbol(symbol));
+    }
+
+    if BUILTIN
+        .iter()
+        .map(Op::operator)
+        .chain(registry.iter().copied())
+        .any(|o| o.name() == operator.name() || o.symbol() == symbol)
+    {
+        return Err(OperatorError::Duplicate(symbol));
+    }
+
+    registry.push(Box::leak(Box::new(operator)));
+
+    Ok(Op::Custom(CustomOp(registry.len() - 1)))
+}
+
+// By name or symbol
+pub fn find(text: &str) -> Option<Op> {
+    BUILTIN
+        .iter()
+        .find(|op| matches(op.operator(), text))
+        .cloned()
+        .or_else(|| {
+            REGISTRY
+                .read()
+                .unwrap()
+                .iter()
+                .position(|o| matches(*o, text))
+                .map(|i| Op::Custom(CustomOp(i)))
+        })
+}
+
+pub fn registered() -> Vec<Op> {
+    let custom = (0..REGISTRY.read().unwrap().len()).map(|i| Op::Custom(CustomOp(i)));
+
+    BUILTIN.iter().cloned().chain(custom).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::eval;
+    use crate::parser::parse;
+    use crate::solver_v2::solutions2;
+
+    // Absolute difference - binds less tightly than add
+    struct Diff;
+
+    impl Operator for Diff {
+        fn name(&self) -> &str {
+            "diff"
+        }
+
+        fn symbol(&self) -> &str {
+            "<>"
+        }
+
+        fn precedence(&self) -> u8 {
+            0
+        }
+
+        fn commutative(&self) -> bool {
+            true
+        }
+
+        fn apply(&self, a: i64, b: i64) -> Result<i64, EvalError> {
+            Ok((a - b).abs())
+        }
+    }
+
+    #[test]
+    fn builtin_lookup() {
+        assert_eq!(find("+"), Some(Op::Add));
+        assert_eq!(find("mod"), Some(Op::Mod));
+        assert_eq!(operator(&Op::Exp).symbol(), "^");
+        assert_eq!(register(Add), Ok(Op::Add));
+    }
+
+    #[test]
+    fn register_errors() {
+        struct Plus;
+
+        impl Operator for Plus {
+            fn name(&self) -> &str {
+                "plus"
+            }
+
+            fn symbol(&self) -> &str {
+                "+"
+            }
+
+            fn precedence(&self) -> u8 {
+                1
+            }
+
+            fn apply(&self, a: i64, b: i64) -> Result<i64, EvalError> {
+                Ok(a + b)
+            }
+        }
+
+        assert_eq!(register(Plus), Err(OperatorError::Symbol("+".to_string())));
+    }
+
+    #[test]
+    fn custom_operator() {
+        let diff = register(Diff).unwrap();
+
+        assert_eq!(register(Diff), Ok(diff.clone()));
+        assert_eq!(find("<>"), Some(diff.clone()));
+        assert!(registered().contains(&diff));
+
+        let expr = parse("3 <> 4 + 6").unwrap();
+
+        assert_eq!(expr.to_string(), "3 <> (4 + 6)");
+        assert_eq!(expr.pretty(), "3 <> 4 + 6");
+        assert_eq!(eval(&expr), Ok(7));
+
+        let ops = [Op::Add, diff].to_vec();
+        let (solns, _) = solutions2(&[3, 4, 6], 7, &ops, &Rules::default());
+
+        assert!(solns.iter().any(|s| s.0.to_string() == "3 <> (4 + 6)"));
+        assert!(solns.iter().all(|s| s.0.to_string() != "(4 + 6) <> 3"));
+    }
+}
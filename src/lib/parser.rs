@@ -0,0 +1,347 @@
+use std::iter::Peekable;
+
+use pest::iterators::{Pair, Pairs};
+use pest::Parser;
+use thiserror::Error;
+
+use crate::expr::{Expr, Op};
+use crate::operator::{find, Assoc};
+
+#[derive(pest_derive::Parser)]
+#[grammar = "expr.pest"]
+pub struct ExprParser;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseError {
+    #[error("syntax error at line {line}, column {col}: {message}")]
+    Syntax {
+        line: usize,
+        col: usize,
+        message: String,
+    },
+    #[error("invalid number '{text}' at line {line}, column {col}")]
+    Number {
+        text: String,
+        line: usize,
+        col: usize,
+    },
+    #[error("cannot negate '{text}' at line {line}, column {col}")]
+    Negate {
+        text: String,
+        line: usize,
+        col: usize,
+    },
+    #[error("unknown operator '{text}' at line {line}, column {col}")]
+    Operator {
+        text: String,
+        line: usize,
+        col: usize,
+    },
+    #[error("unexpected {rule} at line {line}, column {col}")]
+    Unexpected {
+        rule: String,
+        line: usize,
+        col: usize,
+    },
+}
+
+impl From<pest::error::Error<Rule>> for ParseError {
+    fn from(e: pest::error::Error<Rule>) -> Self {
+        let (line, col) = match e.line_col {
+            pest::error::LineColLocation::Pos(pos) => pos,
+            pest::error::LineColLocation::Span(start, _) => start,
+        };
+
+        ParseError::Syntax {
+            line,
+            col,
+            message: e.variant.message().to_string(),
+        }
+    }
+}
+
+fn unexpected(pair: &Pair<Rule>) -> ParseError {
+    let (line, col) = pair.line_col();
+
+    ParseError::Unexpected {
+        rule: format!("{:?}", pair.as_rule()),
+        line,
+        col,
+    }
+}
+
+fn parse_primary(primary: Pair<Rule>) -> Result<Expr, ParseError> {
+    match primary.as_rule() {
+        Rule::integer => primary.as_str().parse::<i32>().map(Expr::Val).map_err(|_| {
+            let (line, col) = primary.line_col();
+
+            ParseError::Number {
+                text: primary.as_str().to_string(),
+                line,
+                col,
+            }
+        }),
+        Rule::expr => parse_expr(primary.into_inner()),
+        _ => Err(unexpected(&primary)),
+    }
+}
+
+// A minus directly on a number gives a negative number rather than a Neg.
+// Anything else, even a bracketed number as in -(3), is a Neg - the same tree
+// the solvers build, so printing and parsing it again gives it back.
+fn negate(pair: &Pair<Rule>, rhs: Expr) -> Result<Expr, ParseError> {
+    match rhs {
+        Expr::Val(v) => v.checked_neg().map(Expr::Val).ok_or_else(|| {
+            let (line, col) = pair.line_col();
+
+            ParseError::Negate {
+                text: rhs.to_string(),
+                line,
+                col,
+            }
+        }),
+        _ => Ok(Expr::new_unary(Op::Neg, rhs)),
+    }
+}
+
+// Unary operators bind tighter than any binary one
+fn parse_atom(pairs: &mut Peekable<Pairs<Rule>>) -> Result<Expr, ParseError> {
+    let missing = || ParseError::Syntax {
+        line: 1,
+        col: 1,
+        message: "missing operand".to_string(),
+    };
+
+    let mut prefixes = Vec::<Pair<Rule>>::new();
+    let mut pair = pairs.next().ok_or_else(missing)?;
+
+    while matches!(pair.as_rule(), Rule::unary_minus | Rule::sqrt) {
+        prefixes.push(pair);
+        pair = pairs.next().ok_or_else(missing)?;
+    }
+
+    let mut number = pair.as_rule() == Rule::integer;
+    let mut expr = parse_primary(pair)?;
+
+    while pairs.peek().is_some_and(|p| p.as_rule() == Rule::fact) {
+        pairs.next();
+        expr = Expr::new_unary(Op::Fact, expr);
+        number = false;
+    }
+
+    prefixes.into_iter().rev().try_fold(expr, |expr, prefix| {
+        let directly = std::mem::replace(&mut number, false);
+
+        match prefix.as_rule() {
+            Rule::unary_minus if directly => negate(&prefix, expr),
+            Rule::unary_minus => Ok(Expr::new_unary(Op::Neg, expr)),
+            _ => Ok(Expr::new_unary(Op::Sqrt, expr)),
+        }
+    })
+}
+
+fn parse_op(pair: &Pair<Rule>) -> Result<Op, ParseError> {
+    match pair.as_rule() {
+        Rule::bin_op => find(pair.as_str())
+            .filter(|op| !op.is_unary())
+            .ok_or_else(|| {
+                let (line, col) = pair.line_col();
+
+                ParseError::Operator {
+                    text: pair.as_str().to_string(),
+                    line,
+                    col,
+                }
+            }),
+        _ => Err(unexpected(pair)),
+    }
+}
+
+// Precedence climbing, so registered operators can bind however they like
+// Precedence is widened so the tightest binding operator still has a level above it
+fn climb(pairs: &mut Peekable<Pairs<Rule>>, min_prec: u16) -> Result<Expr, ParseError> {
+    let mut lhs = parse_atom(pairs)?;
+
+    while let Some(pair) = pairs.peek() {
+        let op = parse_op(pair)?;
+        let operator = op.operator();
+        let prec = u16::from(operator.precedence());
+
+        if prec < min_prec {
+            break;
+        }
+
+        pairs.next();
+
+        let next_min = match operator.assoc() {
+            Assoc::Left => prec + 1,
+            Assoc::Right => prec,
+        };
+
+        lhs = Expr::new_expr(op, lhs, climb(pairs, next_min)?);
+    }
+
+    Ok(lhs)
+}
+
+fn parse_expr(pairs: Pairs<Rule>) -> Result<Expr, ParseError> {
+    climb(&mut pairs.peekable(), 0)
+}
+
+pub fn parse(src: &str) -> Result<Expr, ParseError> {
+    let mut pairs = ExprParser::parse(Rule::equation, src)?;
+
+    match pairs.next() {
+        Some(expr) => parse_expr(expr.into_inner()),
+        None => Err(ParseError::Syntax {
+            line: 1,
+            col: 1,
+            message: "empty expression".to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::{eval_freely, EvalError};
+    use crate::operator::{register, Operator};
+
+    #[test]
+    fn neg_number() {
+        let expr = parse("-3 + 2").unwrap();
+
+        assert_eq!(eval_freely(&expr), Ok(-1));
+    }
+
+    #[test]
+    fn neg_round_trip() {
+        // As the solvers build it with --op neg, and a negative tile
+        let neg = Expr::new_unary(Op::Neg, Expr::new_val(3));
+        let val = Expr::new_val(-3);
+
+        assert_eq!(neg.to_string(), "-(3)");
+        assert_eq!(val.to_string(), "-3");
+
+        [neg, val, Expr::new_unary(Op::Neg, Expr::new_val(-3))]
+            .into_iter()
+            .for_each(|expr| {
+                let reparsed = parse(&expr.to_string()).unwrap();
+
+                assert_eq!(format!("{reparsed:?}"), format!("{expr:?}"));
+                assert_eq!(parse(&expr.pretty()).unwrap().to_string(), expr.to_string());
+            });
+    }
+
+    #[test]
+    fn precedence() {
+        let expr = parse("2 + 3 * (4 - 1)").unwrap();
+
+        assert_eq!(eval_freely(&expr), Ok(11));
+    }
+
+    #[test]
+    fn pretty_round_trip() {
+        [
+            "((1 + 2) + 3) - (4 - 5)",
+            "(100 - (7 * 3)) / (2 * 2)",
+            "((10 / 5) / 2) * (8 % (3 ^ 2))",
+            "25 - (50 + 75)",
+        ]
+        .into_iter()
+        .for_each(|src| {
+            let expr = parse(src).unwrap();
+            let reparsed = parse(&expr.pretty()).unwrap();
+
+            // Display is fully bracketed, so this compares the exact tree shape
+            assert_eq!(reparsed.to_string(), expr.to_string());
+        });
+    }
+
+    #[test]
+    fn syntax_error_position() {
+        let res = parse("2 + * 3");
+
+        assert!(matches!(
+            res,
+            Err(ParseError::Syntax {
+                line: 1,
+                col: 5,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn unary_ops() {
+        let expr = parse("(sqrt (7 + 2))! - -3!").unwrap();
+
+        assert_eq!(expr.to_string(), "(sqrt (7 + 2))! - -3!");
+        assert_eq!(eval_freely(&expr), Ok(12));
+        assert_eq!(parse(&expr.pretty()).unwrap().to_string(), expr.to_string());
+
+        assert_eq!(parse("-(3 + 4)").unwrap().to_string(), "-(3 + 4)");
+        assert_eq!(parse("1||2 * 4").unwrap().to_string(), "(1 || 2) * 4");
+        assert!(matches!(
+            parse("3 sqrt 4"),
+            Err(ParseError::Operator { .. })
+        ));
+    }
+
+    #[test]
+    fn unknown_operator() {
+        let res = parse("2 xor 3");
+
+        assert!(matches!(
+            res,
+            Err(ParseError::Operator {
+                line: 1,
+                col: 3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn tightest_precedence() {
+        struct Tight;
+
+        impl Operator for Tight {
+            fn name(&self) -> &str {
+                "tight"
+            }
+
+            fn symbol(&self) -> &str {
+                "@"
+            }
+
+            fn precedence(&self) -> u8 {
+                u8::MAX
+            }
+
+            fn apply(&self, a: i64, b: i64) -> Result<i64, EvalError> {
+                Ok(a * 10 + b)
+            }
+        }
+
+        register(Tight).unwrap();
+
+        let expr = parse("1 + 2 @ 3 @ 4").unwrap();
+
+        assert_eq!(expr.to_string(), "1 + ((2 @ 3) @ 4)");
+    }
+
+    #[test]
+    fn number_too_big() {
+        let res = parse("1 + 99999999999");
+
+        assert!(matches!(
+            res,
+            Err(ParseError::Number {
+                line: 1,
+                col: 5,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,103 @@
+use crate::expr::{eval_freely, Expr, Op};
+use crate::solver_v2::Result;
+
+// Ranking of solutions - simpler solutions have lower scores
+//
+// Scores compare field by field, so fewer tiles always wins, then a shallower
+// tree, then easier operators and finally smaller intermediate values.
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Score {
+    pub tiles: usize,
+    pub depth: usize,
+    pub op_cost: u32,
+    pub largest: i32,
+}
+
+fn op_cost(op: &Op) -> u32 {
+    op.operator().cost()
+}
+
+pub fn score(expr: &Expr) -> Score {
+    match expr {
+        Expr::Val(v) => Score {
+            tiles: 1,
+            depth: 0,
+            op_cost: 0,
+            largest: *v,
+        },
+        Expr::Expr(op, a, b) => {
+            let a = score(a);
+            let b = score(b);
+            let val = eval_freely(expr).unwrap_or(i32::MAX);
+
+            Score {
+                tiles: a.tiles + b.tiles,
+                depth: 1 + a.depth.max(b.depth),
+                op_cost: op_cost(op) + a.op_cost + b.op_cost,
+                largest: val.max(a.largest).max(b.largest),
+            }
+        }
+        Expr::Unary(op, a) => {
+            let a = score(a);
+            let val = eval_freely(expr).unwrap_or(i32::MAX);
+
+            Score {
+                tiles: a.tiles,
+                depth: 1 + a.depth,
+                op_cost: op_cost(op) + a.op_cost,
+                largest: val.max(a.largest),
+            }
+        }
+    }
+}
+
+pub fn rank_solutions(solns: &[Result]) -> Vec<(Result, Score)> {
+    let mut ranked = solns
+        .iter()
+        .map(|s| (s.clone(), score(&s.0)))
+        .collect::<Vec<_>>();
+
+    ranked.sort_by(|a, b| a.1.cmp(&b.1));
+
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::std_ops;
+    use crate::rules::Rules;
+    use crate::solver_v2::solutions2;
+
+    #[test]
+    fn score_nested() {
+        // (4 + 29) * 30
+        let expr = Expr::new_expr(
+            Op::Mul,
+            Expr::new_expr(Op::Add, Expr::new_val(4), Expr::new_val(29)),
+            Expr::new_val(30),
+        );
+
+        let exp = Score {
+            tiles: 3,
+            depth: 2,
+            op_cost: 4,
+            largest: 990,
+        };
+
+        assert_eq!(score(&expr), exp);
+    }
+
+    #[test]
+    fn fewer_tiles_first() {
+        let input = [1, 2, 3];
+
+        let (solns, _) = solutions2(&input, 6, &std_ops(), &Rules::default());
+        let ranked = rank_solutions(&solns);
+
+        assert_eq!(ranked.len(), solns.len());
+        assert_eq!(ranked[0].1.tiles, 2);
+        assert!(ranked.windows(2).all(|w| w[0].1 <= w[1].1));
+    }
+}
@@ -0,0 +1,343 @@
+use std::fmt::Display;
+
+use num_rational::Ratio;
+
+use crate::combinatorics::{choices, split};
+use crate::expr::{
+    check_concat, concatenable, ordered, step, unary_step, EvalError, Expr, Op, OpsType, Step,
+};
+use crate::rules::Rules;
+use crate::solver_v2::{keep_nearest, no_nearest, Nearest, Result};
+
+// Exact rational arithmetic - intermediate steps may be fractions
+
+pub type Rational = Ratio<i64>;
+
+pub type RationalResult = (Expr, Rational);
+
+// A value in a step, bracketed when it's a fraction so 6 / (1/4) reads clearly
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fraction(pub Rational);
+
+impl Display for Fraction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_integer() {
+            write!(f, "{}", self.0)
+        } else {
+            write!(f, "({})", self.0)
+        }
+    }
+}
+
+pub fn apply_rational(
+    op: &Op,
+    a: Rational,
+    b: Rational,
+) -> std::result::Result<Rational, EvalError> {
+    let operator = op.operator();
+
+    if operator.arity() != 2 {
+        return Err(EvalError::Rule(step(op, a, b)));
+    }
+
+    operator.apply_rational(a, b)
+}
+
+pub fn apply_rational_unary(op: &Op, a: Rational) -> std::result::Result<Rational, EvalError> {
+    let operator = op.operator();
+
+    if operator.arity() != 1 {
+        return Err(EvalError::Rule(unary_step(op, a)));
+    }
+
+    operator.apply_rational(a, a)
+}
+
+pub fn eval_rational(expr: &Expr) -> std::result::Result<Rational, EvalError> {
+    match expr {
+        Expr::Val(v) => Ok(Rational::from_integer(*v as i64)),
+        Expr::Expr(op, a, b) => {
+            check_concat(op, a, b)?;
+
+            apply_rational(op, eval_rational(a)?, eval_rational(b)?)
+        }
+        Expr::Unary(op, a) => apply_rational_unary(op, eval_rational(a)?),
+    }
+}
+
+fn add_steps_rational(expr: &Expr, steps: &mut Vec<Step<Fraction>>) -> Option<Rational> {
+    match expr {
+        Expr::Val(v) => Some(Rational::from_integer(*v as i64)),
+        Expr::Expr(op, a, b) => {
+            let a = add_steps_rational(a, steps)?;
+            let b = add_steps_rational(b, steps)?;
+            let result = apply_rational(op, a, b).ok()?;
+
+            steps.push(Step {
+                op: op.clone(),
+                a: Fraction(a),
+                b: Some(Fraction(b)),
+                result: Fraction(result),
+            });
+
+            Some(result)
+        }
+        Expr::Unary(op, a) => {
+            let a = add_steps_rational(a, steps)?;
+            let result = apply_rational_unary(op, a).ok()?;
+
+            steps.push(Step {
+                op: op.clone(),
+                a: Fraction(a),
+                b: None,
+                result: Fraction(result),
+            });
+
+            Some(result)
+        }
+    }
+}
+
+// As expr::steps, with fractional steps shown as fractions
+pub fn steps_rational(expr: &Expr) -> Vec<Step<Fraction>> {
+    let mut steps = Vec::<Step<Fraction>>::new();
+
+    add_steps_rational(expr, &mut steps);
+
+    steps
+}
+
+// As expr::valid, but division only has to be exact when the rules say so
+pub fn valid_rational(op: &Op, a: &Rational, b: &Rational, rules: &Rules) -> bool {
+    ordered(op, a, b) && rules.allows_step(op, a, b)
+}
+
+fn allows_rational(val: &Rational, rules: &Rules) -> bool {
+    let within_max = match rules.max_value {
+        // Not abs, which can't negate an i64::MIN numerator
+        Some(max) => {
+            let min = Rational::from_integer(max.saturating_neg());
+
+            val <= &Rational::from_integer(max) && val >= &min
+        }
+        None => true,
+    };
+
+    rules.allows_value(val) && within_max && (rules.allow_fractions || val.is_integer())
+}
+
+fn combine_rational(
+    l: &RationalResult,
+    r: &RationalResult,
+    ops: &OpsType,
+    rules: &Rules,
+) -> Vec<RationalResult> {
+    let mut res = Vec::<RationalResult>::new();
+
+    ops.iter().for_each(|op| {
+        if valid_rational(op, &l.1, &r.1, rules) && concatenable(op, &l.0, &r.0) {
+            match apply_rational(op, l.1, r.1) {
+                Ok(val) if allows_rational(&val, rules) => {
+                    res.push((Expr::new_expr(op.clone(), l.0.clone(), r.0.clone()), val));
+                }
+                _ => (),
+            }
+        }
+    });
+
+    res
+}
+
+fn results_rational(src: &[i32], ops: &OpsType, rules: &Rules) -> Vec<RationalResult> {
+    let mut res = Vec::<RationalResult>::new();
+
+    if src.len() == 1 {
+        res.push((Expr::new_val(src[0]), Rational::from_integer(src[0] as i64)));
+    } else {
+        split(src).into_iter().for_each(|(l, r)| {
+            let rights = results_rational(&r, ops, rules);
+
+            results_rational(&l, ops, rules).iter().for_each(|le| {
+                rights.iter().for_each(|re| {
+                    res.append(&mut combine_rational(le, re, ops, rules));
+                });
+            });
+        });
+    }
+
+    res
+}
+
+// As solutions2, allowing fractional intermediate steps - the target is still an integer
+pub fn solutions_rational(
+    input: &[i32],
+    target: i32,
+    ops: &OpsType,
+    rules: &Rules,
+) -> (Vec<Result>, usize) {
+    let mut total_checked: usize = 0;
+    let mut res = Vec::<Result>::new();
+
+    let target_val = Rational::from_integer(target as i64);
+
+    choices(input).into_iter().for_each(|choice| {
+        results_rational(&choice, ops, rules)
+            .into_iter()
+            .for_each(|result| {
+                total_checked += 1;
+
+                if result.1 == target_val {
+                    res.push((result.0, target));
+                }
+            });
+    });
+
+    (res, total_checked)
+}
+
+// As solutions_rational, but falls back to the closest results when the target
+// can't be reached. Only whole number results count, as an answer has to be
+// one.
+pub fn solutions_rational_nearest(
+    input: &[i32],
+    target: i32,
+    ops: &OpsType,
+    rules: &Rules,
+) -> (Nearest, usize) {
+    let mut total_checked: usize = 0;
+    let mut nearest = no_nearest();
+
+    choices(input).into_iter().for_each(|choice| {
+        results_rational(&choice, ops, rules)
+            .into_iter()
+            .for_each(|result| {
+                total_checked += 1;
+
+                if !result.1.is_integer() {
+                    return;
+                }
+
+                if let Ok(val) = i32::try_from(result.1.to_integer()) {
+                    keep_nearest(&mut nearest, (result.0, val), &target);
+                }
+            });
+    });
+
+    (nearest, total_checked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::std_ops;
+    use crate::parser::parse;
+    use crate::solver_v2::solutions2;
+
+    #[test]
+    fn eval_half() {
+        let expr = Expr::new_expr(Op::Div, Expr::new_val(7), Expr::new_val(2));
+
+        assert_eq!(eval_rational(&expr), Ok(Rational::new(7, 2)));
+        assert_eq!(eval_rational(&expr).unwrap().to_string(), "7/2");
+    }
+
+    #[test]
+    fn steps_with_fractions() {
+        let res = steps_rational(&parse("6 / (1 - 3 / 4)").unwrap())
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            res,
+            vec!["3 / 4 = (3/4)", "1 - (3/4) = (1/4)", "6 / (1/4) = 24"]
+        );
+    }
+
+    #[test]
+    fn eval_div_zero() {
+        let expr = Expr::new_expr(Op::Div, Expr::new_val(7), Expr::new_val(0));
+
+        assert_eq!(
+            eval_rational(&expr),
+            Err(EvalError::DivByZero("7 / 0".to_string()))
+        );
+    }
+
+    #[test]
+    fn eval_mod_overflow() {
+        // (-2) ^ 63 is i64::MIN, which has no positive counterpart
+        let expr = parse("(-2) ^ 63 % -1").unwrap();
+
+        assert!(matches!(eval_rational(&expr), Err(EvalError::Overflow(_))));
+    }
+
+    #[test]
+    fn eval_recip_overflow() {
+        let expr = parse("((-2) ^ 63) ^ -1").unwrap();
+
+        assert!(matches!(eval_rational(&expr), Err(EvalError::Overflow(_))));
+        assert_eq!(
+            eval_rational(&parse("(2 ^ 62) ^ -1").unwrap()),
+            Ok(Rational::new(1, 1 << 62))
+        );
+    }
+
+    #[test]
+    fn max_value_min_numerator() {
+        let rules = Rules {
+            max_value: Some(1000),
+            allow_negatives: true,
+            allow_fractions: true,
+            ..Rules::default()
+        };
+        let min = Rational::from_integer(i64::MIN);
+
+        assert!(!allows_rational(&min, &rules));
+        assert!(allows_rational(&Rational::new(-1000, 1), &rules));
+    }
+
+    #[test]
+    fn nearest_whole() {
+        let rules = Rules {
+            allow_fractions: true,
+            ..Rules::default()
+        };
+
+        let ((nearest, distance), _) = solutions_rational_nearest(&[2, 3], 100, &std_ops(), &rules);
+
+        assert_eq!(distance, 94);
+        assert!(nearest.iter().all(|s| s.1 == 6));
+
+        let ((exact, distance), _) =
+            solutions_rational_nearest(&[1, 3, 4, 6], 24, &std_ops(), &rules);
+
+        assert_eq!(distance, 0);
+        assert!(exact.iter().any(|s| s.0.to_string() == "6 / (1 - (3 / 4))"));
+    }
+
+    #[test]
+    fn needs_fractions() {
+        // 6 / (1 - (3 / 4)) = 24
+        let input = [1, 3, 4, 6];
+
+        let rules = Rules {
+            allow_fractions: true,
+            ..Rules::default()
+        };
+
+        let (solns, _) = solutions_rational(&input, 24, &std_ops(), &rules);
+
+        assert!(solns.iter().any(|s| s.0.to_string() == "6 / (1 - (3 / 4))"));
+        assert!(
+            solutions_rational(&input, 24, &std_ops(), &Rules::default())
+                .0
+                .iter()
+                .all(|s| s.0.to_string() != "6 / (1 - (3 / 4))")
+        );
+        assert!(solutions2(&input, 24, &std_ops(), &Rules::default())
+            .0
+            .iter()
+            .all(|s| s.0.to_string() != "6 / (1 - (3 / 4))"));
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+use crate::expr::{Expr, OpsType};
+use crate::rank::{score, Score};
+use crate::rules::Rules;
+use crate::solver_v3::all_results;
+
+// Every target a set of tiles can make, from a single pass of the search
+
+#[derive(Debug, Clone)]
+pub struct Reach {
+    pub count: usize,
+    pub best: Expr,
+    pub score: Score,
+}
+
+pub fn reachable(
+    input: &[i32],
+    ops: &OpsType,
+    rules: &Rules,
+    range: RangeInclusive<i32>,
+) -> BTreeMap<i32, Reach> {
+    let mut reach = BTreeMap::<i32, Reach>::new();
+
+    all_results(input, ops, rules)
+        .filter(|result| range.contains(&result.1))
+        .for_each(|(expr, val)| {
+            let expr_score = score(&expr);
+
+            reach
+                .entry(val)
+                .and_modify(|r| {
+                    r.count += 1;
+
+                    // Keep the simplest way to make each value
+                    if expr_score < r.score {
+                        r.best = expr.clone();
+                        r.score = expr_score.clone();
+                    }
+                })
+                .or_insert(Reach {
+                    count: 1,
+                    best: expr,
+                    score: expr_score,
+                });
+        });
+
+    reach
+}
+
+pub fn unreachable(reach: &BTreeMap<i32, Reach>, range: RangeInclusive<i32>) -> Vec<i32> {
+    range.filter(|v| !reach.contains_key(v)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::std_ops;
+    use crate::solver_v3::solutions3;
+
+    #[test]
+    fn small_tiles() {
+        let input = [1, 2, 3];
+        let reach = reachable(&input, &std_ops(), &Rules::default(), 1..=10);
+
+        // 3 * (1 + 2) = 9 is the largest that can be made
+        assert_eq!(unreachable(&reach, 1..=10), vec![10]);
+        assert_eq!(
+            reach[&6].count,
+            solutions3(&input, 6, &std_ops(), &Rules::default()).0.len()
+        );
+        assert_eq!(reach[&6].score.tiles, 2);
+    }
+
+    #[test]
+    fn follows_rules() {
+        let rules = Rules {
+            allow_negatives: true,
+            ..Rules::default()
+        };
+
+        // 1 - 2 = -1 only counts when negatives are allowed
+        assert!(!reachable(&[1, 2], &std_ops(), &Rules::default(), -1..=-1).contains_key(&-1));
+        assert!(reachable(&[1, 2], &std_ops(), &rules, -1..=-1).contains_key(&-1));
+    }
+}
@@ -0,0 +1,221 @@
+use num_traits::{One, Zero};
+
+use crate::expr::{apply_unary_vals, apply_vals, step, unary_step, EvalError, Op};
+use crate::number::Number;
+use crate::operator::Operand;
+
+// Which calculations are allowed - the defaults are the solvers' original
+// pruning, official() is the stricter official Countdown rules
+//
+// allow_negatives and allow_zero decide which results a step may give, while
+// require_positive decides whether such a result may then be used in another
+// step. So with negatives allowed and require_positive set, a negative value
+// can only ever be a final answer. x - x is pointless, so it also needs
+// allow_cancel.
+//
+// Integer evaluation always needs exact division, allow_fractions picks the
+// rational solver instead.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    pub allow_negatives: bool,
+    pub allow_zero: bool,
+    pub allow_fractions: bool,
+    pub allow_identity: bool,
+    pub allow_cancel: bool,
+    pub require_positive: bool,
+    // i64 so it suits every number type - a limit wider than the type is no limit
+    pub max_value: Option<i64>,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            allow_negatives: false,
+            allow_zero: true,
+            allow_fractions: false,
+            allow_identity: false,
+            allow_cancel: false,
+            require_positive: false,
+            max_value: None,
+        }
+    }
+}
+
+impl Rules {
+    // Every step gives a positive whole number
+    pub fn official() -> Self {
+        Rules {
+            allow_zero: false,
+            allow_cancel: true,
+            require_positive: true,
+            ..Rules::default()
+        }
+    }
+
+    // Anything that can be calculated is allowed
+    pub fn free() -> Self {
+        Rules {
+            allow_negatives: true,
+            allow_zero: true,
+            allow_fractions: true,
+            allow_identity: true,
+            allow_cancel: true,
+            require_positive: false,
+            max_value: None,
+        }
+    }
+
+    // Checks on the operands, before the step is calculated - each operator
+    // says which operands it allows
+    pub fn allows_step<T: Ord + Zero + One>(&self, op: &Op, a: &T, b: &T) -> bool {
+        let zero = T::zero();
+        let operator = op.operator();
+
+        if self.require_positive && (a <= &zero || b <= &zero) {
+            return false;
+        }
+
+        operator.arity() == 2 && operator.allows(self, Operand::of(a), Operand::of(b), a.cmp(b))
+    }
+
+    // As allows_step, for unary operators
+    pub fn allows_unary<T: Ord + Zero + One>(&self, op: &Op, a: &T) -> bool {
+        let operator = op.operator();
+
+        if self.require_positive && a <= &T::zero() {
+            return false;
+        }
+
+        operator.arity() == 1
+            && operator.allows(
+                self,
+                Operand::of(a),
+                Operand::of(a),
+                std::cmp::Ordering::Equal,
+            )
+    }
+
+    // Checks on the result of a step
+    pub fn allows_value<T: Ord + Zero>(&self, val: &T) -> bool {
+        let zero = T::zero();
+
+        (self.allow_zero || !val.is_zero()) && (self.allow_negatives || val >= &zero)
+    }
+
+    pub fn within_max<N: Number>(&self, val: &N) -> bool {
+        match self.max_value {
+            Some(max) => {
+                let above = N::from_i64(max).is_some_and(|max| val > &max);
+                let below = N::from_i64(max.saturating_neg()).is_some_and(|min| val < &min);
+
+                !above && !below
+            }
+            None => true,
+        }
+    }
+
+    // Checked step under these rules
+    pub fn apply<N: Number>(&self, op: &Op, a: &N, b: &N) -> Result<N, EvalError> {
+        if !self.allows_step(op, a, b) {
+            return Err(EvalError::Rule(step(op, a, b)));
+        }
+
+        let res = apply_vals(op, a, b)?;
+
+        if self.allows_value(&res) && self.within_max(&res) {
+            Ok(res)
+        } else {
+            Err(EvalError::Rule(step(op, a, b)))
+        }
+    }
+
+    pub fn apply_unary<N: Number>(&self, op: &Op, a: &N) -> Result<N, EvalError> {
+        if !self.allows_unary(op, a) {
+            return Err(EvalError::Rule(unary_step(op, a)));
+        }
+
+        let res = apply_unary_vals(op, a)?;
+
+        if self.allows_value(&res) && self.within_max(&res) {
+            Ok(res)
+        } else {
+            Err(EvalError::Rule(unary_step(op, a)))
+        }
+    }
+
+    pub fn allows<N: Number>(&self, op: &Op, a: &N, b: &N) -> bool {
+        self.apply(op, a, b).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn original() {
+        let rules = Rules::default();
+
+        // As the solvers always pruned - zero may be made and used again, but
+        // not by x - x
+        assert_eq!(rules.apply(&Op::Mod, &10, &5), Ok(0));
+        assert_eq!(rules.apply(&Op::Add, &0, &5), Ok(5));
+        assert!(!rules.allows(&Op::Sub, &3, &3));
+        assert!(!rules.allows(&Op::Sub, &3, &10));
+        assert!(!rules.allows(&Op::Mul, &7, &1));
+    }
+
+    #[test]
+    fn official() {
+        let rules = Rules::official();
+
+        assert!(rules.allows(&Op::Sub, &10, &3));
+        assert!(!rules.allows(&Op::Sub, &3, &3));
+        assert!(!rules.allows(&Op::Sub, &3, &10));
+        assert!(!rules.allows(&Op::Mul, &7, &1));
+        assert!(!rules.allows(&Op::Div, &7, &1));
+        assert!(!rules.allows(&Op::Add, &-3, &10));
+    }
+
+    #[test]
+    fn relaxed() {
+        let rules = Rules {
+            allow_negatives: true,
+            allow_zero: true,
+            allow_identity: true,
+            ..Rules::official()
+        };
+
+        assert_eq!(rules.apply(&Op::Sub, &3, &3), Ok(0));
+        assert_eq!(rules.apply(&Op::Sub, &3, &10), Ok(-7));
+        assert_eq!(rules.apply(&Op::Mul, &7, &1), Ok(7));
+
+        // Still can't build on a negative result
+        assert!(!rules.allows(&Op::Add, &-7, &10));
+        assert!(Rules::free().allows(&Op::Add, &-7, &10));
+    }
+
+    #[test]
+    fn max_value() {
+        let rules = Rules {
+            max_value: Some(1000),
+            ..Rules::default()
+        };
+
+        assert_eq!(rules.apply(&Op::Mul, &25, &40), Ok(1000));
+        assert_eq!(
+            rules.apply(&Op::Mul, &25, &50),
+            Err(EvalError::Rule("25 * 50".to_string()))
+        );
+
+        let wide = Rules {
+            max_value: Some(3_000_000_000),
+            ..Rules::default()
+        };
+
+        assert_eq!(wide.apply(&Op::Mul, &50_000i64, &60_000), Ok(3_000_000_000));
+        assert!(!wide.allows(&Op::Mul, &50_000i64, &70_000));
+        assert!(wide.allows(&Op::Mul, &40_000, &50_000));
+    }
+}
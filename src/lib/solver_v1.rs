@@ -46,7 +46,7 @@ pub fn solutions(input: &[i32], target: i32) -> (Vec<Expr>, usize) {
         exprs.into_iter().for_each(|ex| {
             total_checked += 1;
 
-            if let Some(val) = eval(&ex) {
+            if let Ok(val) = eval(&ex) {
                 if val == target {
                     res.push(ex);
                 }
@@ -1,35 +1,73 @@
-use crate::combinatorics::{choices, split};
-use crate::expr::{eval, valid, Expr, OpsType};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use num_traits::Zero;
+
+use crate::canon::{canonicalize, CanonExpr};
+use crate::combinatorics::{choices, choices_iter, split, split_iter};
+use crate::expr::{concatenable, ordered, Expr, OpsType};
+use crate::number::Number;
+use crate::rules::Rules;
 
 // First optimisation
 
-pub type Result = (Expr, i32);
+pub type Result<N = i32> = (Expr<N>, N);
+
+// Results closest to the target and their distance from it - 0 when exact, or
+// when there are no results at all
+pub type Nearest<N = i32> = (Vec<Result<N>>, <N as Number>::Distance);
 
-fn combine2(l: Result, r: Result, ops: &OpsType) -> Vec<Result> {
-    let mut res = Vec::<Result>::new();
+pub(crate) fn combine2<N: Number>(
+    l: Result<N>,
+    r: Result<N>,
+    ops: &OpsType,
+    rules: &Rules,
+) -> Vec<Result<N>> {
+    let mut res = Vec::<Result<N>>::new();
 
     ops.iter().for_each(|op| {
         // Check if each expression is valid and keep it and its value
-        if valid(&op, l.1, r.1) {
-            let expr = Expr::new_expr(op.clone(), l.0.clone(), r.0.clone());
-            let val = eval(&expr).unwrap();
-            res.push((expr, val));
+        if ordered(op, &l.1, &r.1) && concatenable(op, &l.0, &r.0) {
+            if let Ok(val) = rules.apply(op, &l.1, &r.1) {
+                res.push((Expr::new_expr(op.clone(), l.0.clone(), r.0.clone()), val));
+            }
         }
     });
 
     res
 }
 
-fn results(src: &[i32], ops: &OpsType) -> Vec<Result> {
-    let mut res = Vec::<Result>::new();
+// The result along with each unary operation applied to it. Only applied once
+// to each result, so chains like sqrt sqrt sqrt can't go on forever.
+pub(crate) fn unary<N: Number>(r: Result<N>, ops: &OpsType, rules: &Rules) -> Vec<Result<N>> {
+    let mut res = ops
+        .iter()
+        .filter(|op| op.is_unary())
+        .filter_map(|op| {
+            rules
+                .apply_unary(op, &r.1)
+                .ok()
+                .map(|val| (Expr::new_unary(op.clone(), r.0.clone()), val))
+        })
+        .collect::<Vec<_>>();
+
+    res.insert(0, r);
+
+    res
+}
+
+fn results<N: Number>(src: &[N], ops: &OpsType, rules: &Rules) -> Vec<Result<N>> {
+    let mut res = Vec::<Result<N>>::new();
 
     if src.len() == 1 {
-        res.push((Expr::new_val(src[0].clone()), src[0]));
+        res.push((Expr::new_val(src[0].clone()), src[0].clone()));
     } else {
         split(src).into_iter().for_each(|(l, r)| {
-            results(&l, ops).into_iter().for_each(|le| {
-                results(&r, ops).into_iter().for_each(|re| {
-                    combine2(le.clone(), re.clone(), ops)
+            results(&l, ops, rules).into_iter().for_each(|le| {
+                results(&r, ops, rules).into_iter().for_each(|re| {
+                    combine2(le.clone(), re.clone(), ops, rules)
                         .into_iter()
                         .for_each(|e| {
                             res.push(e);
@@ -39,15 +77,24 @@ fn results(src: &[i32], ops: &OpsType) -> Vec<Result> {
         });
     }
 
+    if ops.iter().any(|op| op.is_unary()) {
+        res = res.into_iter().flat_map(|r| unary(r, ops, rules)).collect();
+    }
+
     res
 }
 
-pub fn solutions2(input: &[i32], target: i32, ops: &OpsType) -> (Vec<Result>, usize) {
+pub fn solutions2<N: Number>(
+    input: &[N],
+    target: N,
+    ops: &OpsType,
+    rules: &Rules,
+) -> (Vec<Result<N>>, usize) {
     let mut total_checked: usize = 0;
-    let mut res = Vec::<Result>::new();
+    let mut res = Vec::<Result<N>>::new();
 
-    choices(&input).into_iter().for_each(|choice| {
-        let results = results(&choice, ops);
+    choices(input).into_iter().for_each(|choice| {
+        let results = results(&choice, ops, rules);
 
         results.into_iter().for_each(|result| {
             total_checked += 1;
@@ -61,14 +108,248 @@ pub fn solutions2(input: &[i32], target: i32, ops: &OpsType) -> (Vec<Result>, us
     (res, total_checked)
 }
 
-pub fn dedup(all_solns: &Vec<Result>) -> (Vec<Result>, Vec<String>) {
-    let mut deduped = Vec::<Result>::new();
+// Lazy version of results - only the right hand side of each split is collected
+fn results_iter<'a>(
+    src: &[i32],
+    ops: &'a OpsType,
+    rules: &'a Rules,
+) -> Box<dyn Iterator<Item = Result> + 'a> {
+    let res = results_iter_binary(src, ops, rules);
+
+    if ops.iter().any(|op| op.is_unary()) {
+        Box::new(res.flat_map(move |r| unary(r, ops, rules)))
+    } else {
+        res
+    }
+}
+
+fn results_iter_binary<'a>(
+    src: &[i32],
+    ops: &'a OpsType,
+    rules: &'a Rules,
+) -> Box<dyn Iterator<Item = Result> + 'a> {
+    if src.len() == 1 {
+        Box::new(std::iter::once((Expr::new_val(src[0]), src[0])))
+    } else {
+        Box::new(split_iter(src).flat_map(move |(l, r)| {
+            // Every left result is paired with every right result, so the right
+            // side is collected once and shared rather than rebuilt for each
+            // left result. This trades memory for time - the largest Vec held
+            // is the results of the right subset, which has at most one tile
+            // fewer than the input.
+            let rights = Rc::new(results_iter(&r, ops, rules).collect::<Vec<_>>());
+
+            results_iter(&l, ops, rules).flat_map(move |le| {
+                let rights = rights.clone();
+
+                (0..rights.len())
+                    .flat_map(move |i| combine2(le.clone(), rights[i].clone(), ops, rules))
+            })
+        }))
+    }
+}
+
+// Solutions are produced as they are found, so callers can stop early with take()
+pub fn solutions_iter<'a>(
+    input: &[i32],
+    target: i32,
+    ops: &'a OpsType,
+    rules: &'a Rules,
+) -> impl Iterator<Item = Result> + 'a {
+    choices_iter(input)
+        .flat_map(move |choice| results_iter(&choice, ops, rules))
+        .filter(move |result| result.1 == target)
+}
+
+// As solutions2_nearest, but stops once limit solutions have been found. The
+// nearest are kept along the way, so a target that can't be reached only
+// needs the one search.
+pub fn solutions2_limit(
+    input: &[i32],
+    target: i32,
+    ops: &OpsType,
+    rules: &Rules,
+    limit: usize,
+) -> (Nearest, usize) {
+    let mut total_checked: usize = 0;
+    let mut nearest = no_nearest();
+    let mut found: usize = 0;
+
+    for result in choices_iter(input).flat_map(|choice| results_iter(&choice, ops, rules)) {
+        if found == limit {
+            break;
+        }
+
+        total_checked += 1;
+
+        if result.1 == target {
+            found += 1;
+        }
+
+        keep_nearest(&mut nearest, result, &target);
+    }
+
+    (nearest, total_checked)
+}
+
+pub(crate) fn no_nearest<N: Number>() -> Nearest<N> {
+    (Vec::<Result<N>>::new(), N::Distance::zero())
+}
+
+pub(crate) fn keep_nearest<N: Number>(nearest: &mut Nearest<N>, result: Result<N>, target: &N) {
+    let distance = result.1.abs_diff(target);
+
+    if nearest.0.is_empty() || distance < nearest.1 {
+        nearest.0.clear();
+        nearest.1 = distance;
+    } else if distance != nearest.1 {
+        return;
+    }
+
+    nearest.0.push(result);
+}
+
+// As solutions2, but falls back to the closest results when the target can't be reached
+pub fn solutions2_nearest<N: Number>(
+    input: &[N],
+    target: N,
+    ops: &OpsType,
+    rules: &Rules,
+) -> (Nearest<N>, usize) {
+    let mut total_checked: usize = 0;
+    let mut nearest = no_nearest();
+
+    choices(input).into_iter().for_each(|choice| {
+        let results = results(&choice, ops, rules);
+
+        results.into_iter().for_each(|result| {
+            total_checked += 1;
+
+            keep_nearest(&mut nearest, result, &target);
+        });
+    });
+
+    (nearest, total_checked)
+}
+
+// Runs f over every choice on a pool of threads. Each thread takes the next
+// unclaimed choice when it is free and outputs are returned in choice order.
+fn par_choices<N, T, F>(input: &[N], threads: usize, f: F) -> Vec<T>
+where
+    N: Clone + Sync,
+    T: Send,
+    F: Fn(&[N]) -> T + Sync,
+{
+    let choices = choices(input);
+    let next = AtomicUsize::new(0);
+
+    let mut outputs = thread::scope(|scope| {
+        let workers = (0..threads.max(1))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut outputs = Vec::<(usize, T)>::new();
+
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        if i >= choices.len() {
+                            break;
+                        }
+
+                        outputs.push((i, f(&choices[i])));
+                    }
+
+                    outputs
+                })
+            })
+            .collect::<Vec<_>>();
+
+        workers
+            .into_iter()
+            .flat_map(|w| w.join().expect("solver thread panicked"))
+            .collect::<Vec<_>>()
+    });
+
+    outputs.sort_by_key(|(i, _)| *i);
+    outputs.into_iter().map(|(_, output)| output).collect()
+}
+
+// Parallel solutions2 - gives identical results in the same order
+pub fn solutions2_par<N: Number>(
+    input: &[N],
+    target: N,
+    ops: &OpsType,
+    rules: &Rules,
+    threads: usize,
+) -> (Vec<Result<N>>, usize) {
+    let mut total_checked: usize = 0;
+    let mut res = Vec::<Result<N>>::new();
+
+    par_choices(input, threads, |choice| {
+        let results = results(choice, ops, rules);
+        let checked = results.len();
+
+        let found = results
+            .into_iter()
+            .filter(|result| result.1 == target)
+            .collect::<Vec<_>>();
+
+        (found, checked)
+    })
+    .into_iter()
+    .for_each(|(mut found, checked)| {
+        total_checked += checked;
+        res.append(&mut found);
+    });
+
+    (res, total_checked)
+}
+
+// Parallel solutions2_nearest - gives identical results in the same order
+pub fn solutions2_nearest_par<N: Number>(
+    input: &[N],
+    target: N,
+    ops: &OpsType,
+    rules: &Rules,
+    threads: usize,
+) -> (Nearest<N>, usize) {
+    let mut total_checked: usize = 0;
+    let mut nearest = no_nearest();
+
+    par_choices(input, threads, |choice| {
+        let mut nearest = no_nearest();
+        let results = results(choice, ops, rules);
+        let checked = results.len();
+
+        results.into_iter().for_each(|result| {
+            keep_nearest(&mut nearest, result, &target);
+        });
+
+        (nearest, checked)
+    })
+    .into_iter()
+    .for_each(|(found, checked)| {
+        total_checked += checked;
+        found.0.into_iter().for_each(|result| {
+            keep_nearest(&mut nearest, result, &target);
+        });
+    });
+
+    (nearest, total_checked)
+}
+
+// Duplicates are found by canonical form, so regrouped and reordered solutions match
+pub fn dedup<N: Number>(all_solns: &[Result<N>]) -> (Vec<Result<N>>, Vec<String>) {
+    let mut deduped = Vec::<Result<N>>::new();
     let mut dups_log = Vec::<String>::new();
+    let mut seen = HashMap::<CanonExpr<N>, usize>::new();
+
+    all_solns.iter().cloned().for_each(|s| {
+        let canon = canonicalize(&s.0);
 
-    all_solns.clone().into_iter().for_each(|s| {
-        if let Some(dup) = deduped.iter().find(|&dr| dr == &s) {
-            dups_log.push(format!("{} == {}", s.0, dup.0));
+        if let Some(&dup) = seen.get(&canon) {
+            dups_log.push(format!("{} == {}", s.0, deduped[dup].0));
         } else {
+            seen.insert(canon, deduped.len());
             deduped.push(s);
         }
     });
@@ -80,12 +361,13 @@ pub fn dedup(all_solns: &Vec<Result>) -> (Vec<Result>, Vec<String>) {
 mod tests {
     use super::*;
     use crate::expr::{std_ops, Op};
+    use num_bigint::BigInt;
 
     #[test]
     fn want_608_v2() {
         let input = [50, 25, 75, 100, 4, 1];
 
-        let solns = solutions2(&input, TARGET, &std_ops());
+        let solns = solutions2(&input, TARGET, &std_ops(), &Rules::default());
 
         const TARGET: i32 = 608;
 
@@ -102,6 +384,149 @@ mod tests {
         });
     }
 
+    #[test]
+    fn iter_matches_solutions2() {
+        let input = [50, 25, 75, 4, 1];
+        let ops = std_ops();
+
+        let solns = solutions2(&input, 329, &ops, &Rules::default());
+        let lazy = solutions_iter(&input, 329, &ops, &Rules::default()).collect::<Vec<_>>();
+
+        assert_eq!(lazy.len(), solns.0.len());
+        assert_eq!(dedup(&lazy).0.len(), dedup(&solns.0).0.len());
+    }
+
+    #[test]
+    fn iter_take_first() {
+        let input = [50, 25, 75, 100, 4, 1];
+
+        let first = solutions_iter(&input, 608, &std_ops(), &Rules::default())
+            .take(1)
+            .collect::<Vec<_>>();
+
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].1, 608);
+    }
+
+    #[test]
+    fn limit_stops_early() {
+        let input = [50, 25, 75, 100, 4, 1];
+        let ops = std_ops();
+
+        let all = solutions2(&input, 608, &ops, &Rules::default());
+        let ((first, _), checked) = solutions2_limit(&input, 608, &ops, &Rules::default(), 1);
+
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].1, 608);
+        assert!(checked < all.1);
+
+        let ((many, _), checked) = solutions2_limit(&input, 608, &ops, &Rules::default(), 1000);
+
+        assert_eq!(many.len(), all.0.len());
+        assert_eq!(checked, all.1);
+
+        let (nearest, checked) = solutions2_limit(&[1, 2], 10, &ops, &Rules::default(), 1);
+
+        assert_eq!(
+            (nearest, checked),
+            solutions2_nearest(&[1, 2], 10, &ops, &Rules::default())
+        );
+    }
+
+    #[test]
+    fn par_matches_sequential() {
+        let input = [50, 25, 75, 100, 4, 1];
+        let ops = std_ops();
+
+        let seq = solutions2(&input, 608, &ops, &Rules::default());
+        let par = solutions2_par(&input, 608, &ops, &Rules::default(), 4);
+
+        assert_eq!(par.1, seq.1);
+        assert_eq!(
+            par.0.iter().map(|s| s.0.to_string()).collect::<Vec<_>>(),
+            seq.0.iter().map(|s| s.0.to_string()).collect::<Vec<_>>()
+        );
+
+        let seq = solutions2_nearest(&[1, 2, 3], 10, &ops, &Rules::default());
+        let par = solutions2_nearest_par(&[1, 2, 3], 10, &ops, &Rules::default(), 3);
+
+        assert_eq!(par.0 .1, seq.0 .1);
+        assert_eq!(par.0 .0.len(), seq.0 .0.len());
+    }
+
+    #[test]
+    fn nearest_exact() {
+        let input = [50, 25, 75, 100, 4, 1];
+
+        let ((solns, distance), checked) =
+            solutions2_nearest(&input, 608, &std_ops(), &Rules::default());
+        let exact = solutions2(&input, 608, &std_ops(), &Rules::default());
+
+        assert_eq!(distance, 0u32);
+        assert_eq!(solns.len(), exact.0.len());
+        assert_eq!(checked, exact.1);
+    }
+
+    #[test]
+    fn nearest_unreachable() {
+        let input = [1, 2];
+
+        let ((solns, distance), _) = solutions2_nearest(&input, 10, &std_ops(), &Rules::default());
+
+        assert_eq!(distance, 7u32);
+        assert!(solns.iter().all(|s| s.1 == 3));
+        assert!(solutions2(&input, 10, &std_ops(), &Rules::default())
+            .0
+            .is_empty());
+    }
+
+    #[test]
+    fn wider_numbers() {
+        // (2 ^ 32) / 4 overflows an i32 part way
+        let input = [2, 32, 4];
+        let ops = [Op::Div, Op::Exp].to_vec();
+        let expr = "(2 ^ 32) / 4";
+
+        let (narrow, _) = solutions2(&input, 1 << 30, &ops, &Rules::default());
+        let (wide, _) = solutions2(&input.map(i64::from), 1 << 30, &ops, &Rules::default());
+        let (big, _) = solutions2(
+            &input.map(BigInt::from),
+            BigInt::from(1 << 30),
+            &ops,
+            &Rules::default(),
+        );
+
+        assert!(narrow.iter().all(|s| s.0.to_string() != expr));
+        assert!(wide.iter().any(|s| s.0.to_string() == expr));
+        assert!(big.iter().any(|s| s.0.to_string() == expr));
+    }
+
+    #[test]
+    fn unary_ops() {
+        let ops = [Op::Add, Op::Mul, Op::Fact, Op::Sqrt].to_vec();
+
+        let (solns, _) = solutions2(&[3, 4], 30, &ops, &Rules::default());
+        assert!(solns.iter().any(|s| s.0.to_string() == "3! + 4!"));
+
+        let (solns, _) = solutions2(&[9, 4], 6, &ops, &Rules::default());
+        assert!(solns.iter().any(|s| s.0.to_string() == "sqrt 4 * sqrt 9"));
+
+        let found = solutions_iter(&[9, 4], 6, &ops, &Rules::default()).count();
+        assert_eq!(found, solns.len());
+    }
+
+    #[test]
+    fn concatenation() {
+        let ops = [Op::Mul, Op::Cat].to_vec();
+
+        let (solns, _) = solutions2(&[1, 2, 4], 48, &ops, &Rules::default());
+        assert!(solns.iter().any(|s| s.0.to_string() == "4 * (1 || 2)"));
+
+        // Only tiles can be joined, not 2 * 4
+        let (solns, _) = solutions2(&[1, 2, 4], 18, &ops, &Rules::default());
+        assert!(solns.is_empty());
+    }
+
     #[test]
     fn add_dups() {
         let input = [1, 2, 5];
@@ -111,7 +536,7 @@ mod tests {
         let mut res = Vec::<Result>::new();
 
         choices(&input).into_iter().for_each(|choice| {
-            let results = results(&choice, &ops);
+            let results = results(&choice, &ops, &Rules::default());
 
             results.into_iter().for_each(|result| {
                 total += 1;
@@ -0,0 +1,274 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::expr::{Expr, OpsType};
+use crate::number::Number;
+use crate::rules::Rules;
+use crate::solver_v2::{combine2, keep_nearest, no_nearest, Nearest, Result};
+
+// Second optimisation - memoised subsets
+//
+// Each sub-multiset of the input (a bitmask over the input indices) has its
+// results computed once and larger sets are built by combining disjoint masks.
+// Masks with the same values (duplicate tiles) share a single result set.
+
+fn mask_key<N: Number>(input: &[N], mask: usize) -> Vec<N> {
+    let mut key = input
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| mask & (1 << i) != 0)
+        .map(|(_, v)| v.clone())
+        .collect::<Vec<_>>();
+    key.sort();
+
+    key
+}
+
+// Each distinct way to split a mask into two canonical masks, the left one
+// walking every non-empty proper submask
+fn splits(mask: usize, canon: &[usize]) -> Vec<(usize, usize)> {
+    let mut splits = Vec::<(usize, usize)>::new();
+    let mut seen = HashSet::<(usize, usize)>::new();
+
+    let mut l = (mask - 1) & mask;
+    while l > 0 {
+        let split = (canon[l], canon[mask ^ l]);
+
+        if seen.insert(split) {
+            splits.push(split);
+        }
+
+        l = (l - 1) & mask;
+    }
+
+    splits
+}
+
+// Returns the results for every mask, indexed by mask, along with the
+// canonical mask for each one. Only canonical masks have their results filled,
+// apart from the whole input - nothing reuses it, so its results are left to
+// the caller to stream.
+fn results_by_mask<N: Number>(
+    input: &[N],
+    ops: &OpsType,
+    rules: &Rules,
+) -> (Vec<Vec<Result<N>>>, Vec<usize>) {
+    let num_masks = 1usize << input.len();
+    let full = num_masks - 1;
+
+    let mut memo = vec![Vec::<Result<N>>::new(); num_masks];
+    let mut canon = (0..num_masks).collect::<Vec<_>>();
+    let mut seen = HashMap::<Vec<N>, usize>::new();
+
+    (1..num_masks).for_each(|mask| {
+        let key = mask_key(input, mask);
+
+        if let Some(&first) = seen.get(&key) {
+            canon[mask] = first;
+            return;
+        }
+        seen.insert(key, mask);
+
+        if mask.is_power_of_two() {
+            let val = input[mask.trailing_zeros() as usize].clone();
+            memo[mask].push((Expr::new_val(val.clone()), val));
+        } else if mask != full {
+            let mut res = Vec::<Result<N>>::new();
+
+            splits(mask, &canon).into_iter().for_each(|(cl, cr)| {
+                memo[cl].iter().for_each(|le| {
+                    memo[cr].iter().for_each(|re| {
+                        res.append(&mut combine2(le.clone(), re.clone(), ops, rules));
+                    });
+                });
+            });
+
+            memo[mask] = res;
+        }
+    });
+
+    (memo, canon)
+}
+
+// Every result for every distinct sub-multiset of the input. Results for the
+// whole input are made as they are needed rather than stored.
+pub fn all_results<N: Number>(
+    input: &[N],
+    ops: &OpsType,
+    rules: &Rules,
+) -> impl Iterator<Item = Result<N>> {
+    let (memo, canon) = results_by_mask(input, ops, rules);
+    let full = memo.len() - 1;
+
+    let top = if full.count_ones() > 1 {
+        splits(full, &canon)
+    } else {
+        Vec::new()
+    };
+
+    let memo = Rc::new(memo);
+    let (ops, rules) = (Rc::new(ops.clone()), Rc::new(rules.clone()));
+
+    let stored = {
+        let memo = Rc::clone(&memo);
+
+        (1..memo.len())
+            .filter(move |mask| canon[*mask] == *mask)
+            .flat_map(move |mask| {
+                let memo = Rc::clone(&memo);
+
+                (0..memo[mask].len()).map(move |i| memo[mask][i].clone())
+            })
+    };
+
+    let streamed = top.into_iter().flat_map(move |(cl, cr)| {
+        let (memo, ops, rules) = (Rc::clone(&memo), Rc::clone(&ops), Rc::clone(&rules));
+
+        (0..memo[cl].len()).flat_map(move |i| {
+            let (memo, ops, rules) = (Rc::clone(&memo), Rc::clone(&ops), Rc::clone(&rules));
+
+            (0..memo[cr].len())
+                .flat_map(move |j| combine2(memo[cl][i].clone(), memo[cr][j].clone(), &ops, &rules))
+        })
+    });
+
+    stored.chain(streamed)
+}
+
+pub fn solutions3<N: Number>(
+    input: &[N],
+    target: N,
+    ops: &OpsType,
+    rules: &Rules,
+) -> (Vec<Result<N>>, usize) {
+    let mut total_checked: usize = 0;
+    let mut res = Vec::<Result<N>>::new();
+
+    all_results(input, ops, rules).for_each(|result| {
+        total_checked += 1;
+
+        if result.1 == target {
+            res.push(result);
+        }
+    });
+
+    (res, total_checked)
+}
+
+// As solutions3, but falls back to the closest results when the target can't be reached
+pub fn solutions3_nearest<N: Number>(
+    input: &[N],
+    target: N,
+    ops: &OpsType,
+    rules: &Rules,
+) -> (Nearest<N>, usize) {
+    let mut total_checked: usize = 0;
+    let mut nearest = no_nearest();
+
+    all_results(input, ops, rules).for_each(|result| {
+        total_checked += 1;
+
+        keep_nearest(&mut nearest, result, &target);
+    });
+
+    (nearest, total_checked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::{std_ops, Op};
+    use crate::solver_v2::{dedup, solutions2};
+
+    #[test]
+    fn mask_key_sorted() {
+        assert_eq!(mask_key(&[5, 1, 3, 1], 0b1011), vec![1, 1, 5]);
+    }
+
+    #[test]
+    fn whole_input_streamed() {
+        let ops = std_ops();
+        let rules = Rules::default();
+
+        assert_eq!(all_results::<i32>(&[], &ops, &rules).count(), 0);
+        assert_eq!(
+            all_results(&[5], &ops, &rules)
+                .map(|r| r.1)
+                .collect::<Vec<_>>(),
+            vec![5]
+        );
+        // 3, 4, then 3 + 4, 4 - 3 and 3 * 4
+        assert_eq!(all_results(&[3, 4], &ops, &rules).count(), 5);
+    }
+
+    #[test]
+    fn matches_v2_three() {
+        let input = [1, 2, 3];
+        let ops = std_ops();
+
+        let v2 = solutions2(&input, 6, &ops, &Rules::default());
+        let v3 = solutions3(&input, 6, &ops, &Rules::default());
+
+        assert_eq!(v3.0.len(), v2.0.len());
+        assert_eq!(v3.1, v2.1);
+    }
+
+    #[test]
+    fn duplicate_tiles_shared() {
+        let input = [2, 2];
+        let ops = [Op::Add, Op::Mul].to_vec();
+
+        let (solns, checked) = solutions3(&input, 4, &ops, &Rules::default());
+
+        // 2, 2 + 2 and 2 * 2 - the second 2 is not a distinct subset
+        assert_eq!(checked, 3);
+        assert_eq!(solns.len(), 2);
+    }
+
+    #[test]
+    fn nearest_matches_v2() {
+        let input = [25, 50, 75, 100, 3, 6];
+        let ops = std_ops();
+
+        let v2 = crate::solver_v2::solutions2_nearest(&input, 1000, &ops, &Rules::default());
+        let v3 = solutions3_nearest(&input, 1000, &ops, &Rules::default());
+
+        assert_eq!(v3.0 .1, v2.0 .1);
+        assert_eq!(v3.0 .0.len(), v2.0 .0.len());
+    }
+
+    #[test]
+    fn nearest_beyond_i32() {
+        let input = [i32::MAX as i64, 2];
+        let target = 2 * i32::MAX as i64 + 4;
+
+        let ((nearest, distance), _) =
+            solutions3_nearest(&input, target, &std_ops(), &Rules::default());
+
+        assert_eq!(distance, 4u64);
+        assert!(nearest.iter().all(|s| s.1 == 2 * i32::MAX as i64));
+    }
+
+    #[test]
+    fn want_608_v3() {
+        let input = [50, 25, 75, 100, 4, 1];
+
+        let solns = solutions3(&input, TARGET, &std_ops(), &Rules::default());
+
+        const TARGET: i32 = 608;
+
+        println!(
+            "{} solutions to make {} from {:?} - {} checked",
+            solns.0.len(),
+            TARGET,
+            input,
+            solns.1
+        );
+
+        let v2 = solutions2(&input, TARGET, &std_ops(), &Rules::default());
+
+        assert_eq!(solns.0.len(), v2.0.len());
+        assert_eq!(solns.1, v2.1);
+        assert_eq!(dedup(&solns.0).0.len(), dedup(&v2.0).0.len());
+    }
+}